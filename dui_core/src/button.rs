@@ -0,0 +1,194 @@
+use vello::kurbo::Rect;
+
+use crate::{
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    environment::BackgroundBrush,
+    event::{Event, MouseButton},
+    layout::{get_id_manger, get_id_manger_mut},
+    modifier::ModifierContent,
+    theme::Theme,
+    view::{Element, PaddingImpl, View},
+};
+
+/// Opacity of the theme's accent color behind a pressed button
+const PRESSED_OPACITY: f32 = 0.4;
+
+/// The state of a button its style is drawn for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ButtonConfiguration {
+    /// Whether the button is held down with the cursor still over it
+    pub is_pressed: bool,
+}
+
+/// How a button looks, set with `.button_style(..)` so controls can share a look without wrapping each label
+pub trait ButtonStyle {
+    fn body<L: View>(
+        &self,
+        label: ModifierContent<L>,
+        configuration: ButtonConfiguration,
+    ) -> impl View;
+}
+
+/// The theme's surface color around the label, tinted with the accent color while pressed
+pub struct DefaultButtonStyle;
+
+impl ButtonStyle for DefaultButtonStyle {
+    fn body<L: View>(
+        &self,
+        label: ModifierContent<L>,
+        configuration: ButtonConfiguration,
+    ) -> impl View {
+        DefaultButtonBackground {
+            view: label.padding((10.0, 5.0)),
+            pressed: configuration.is_pressed,
+        }
+    }
+}
+
+/// A background read from the theme when drawn, as the style is built without access to the environment
+struct DefaultButtonBackground<V: View> {
+    view: V,
+    pressed: bool,
+}
+
+impl<V: View> Element for DefaultButtonBackground<V> {}
+
+impl<V: View> View for DefaultButtonBackground<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let colors = dctx.env::<Theme>().colors;
+        let color = if self.pressed {
+            colors.accent.with_alpha_factor(PRESSED_OPACITY)
+        } else {
+            colors.surface
+        };
+        dctx.environment.set::<BackgroundBrush>(color.into());
+
+        self.view.draw(dctx);
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub(crate) struct ButtonState {
+    /// Whether a press started on the button and hasn't been released yet
+    pressed: bool,
+    /// Whether the cursor is over the button during that press, the press only shows while it is
+    over: bool,
+}
+
+/// Calls its action when clicked, a press only counts if it's released over the button
+pub struct Button<L: View, S: ButtonStyle = DefaultButtonStyle> {
+    label: L,
+    action: Box<dyn Fn()>,
+    style: S,
+}
+
+impl<L: View> Button<L> {
+    pub fn new(label: L, action: impl Fn() + 'static) -> Button<L> {
+        Button {
+            label,
+            action: Box::new(action),
+            style: DefaultButtonStyle,
+        }
+    }
+}
+
+impl<L: View, S: ButtonStyle> Button<L, S> {
+    pub fn button_style<T: ButtonStyle>(self, style: T) -> Button<L, T> {
+        Button {
+            label: self.label,
+            action: self.action,
+            style,
+        }
+    }
+
+    fn body(&self, state: ButtonState) -> impl View + '_ {
+        let configuration = ButtonConfiguration {
+            is_pressed: state.pressed && state.over,
+        };
+
+        self.style.body(ModifierContent(&self.label), configuration)
+    }
+}
+
+impl<L: View, S: ButtonStyle> Element for Button<L, S> {}
+
+impl<L: View, S: ButtonStyle> View for Button<L, S> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let state = get_id_manger()
+            .state::<ButtonState>(lctx.id())
+            .copied()
+            .unwrap_or_default();
+
+        self.body(state).layout(lctx, available_rect)
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        let state = get_id_manger()
+            .state::<ButtonState>(dctx.id())
+            .copied()
+            .unwrap_or_default();
+
+        self.body(state).draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let id = ectx.id();
+        let state = get_id_manger()
+            .state::<ButtonState>(id.clone())
+            .copied()
+            .unwrap_or_default();
+
+        if self.body(state).event(ectx, event) {
+            return true;
+        }
+
+        let bounds = get_id_manger().get_layout(id.clone()).border_bounds;
+
+        match event {
+            Event::MouseDown {
+                position,
+                button: MouseButton::Left,
+                ..
+            } if bounds.contains(*position) => {
+                *get_id_manger_mut().state_mut::<ButtonState>(id) = ButtonState {
+                    pressed: true,
+                    over: true,
+                };
+                true
+            }
+            // Still pressed from a release the button never saw, e.g. one outside the window
+            Event::MouseDown { .. } if state.pressed => {
+                *get_id_manger_mut().state_mut::<ButtonState>(id) = ButtonState::default();
+                false
+            }
+            Event::MouseMoved { position } if state.pressed => {
+                let over = bounds.contains(*position);
+                get_id_manger_mut().state_mut::<ButtonState>(id).over = over;
+
+                // Only redraw when the cursor crosses the edge
+                over != state.over
+            }
+            Event::MouseUp {
+                position,
+                button: MouseButton::Left,
+            } if state.pressed => {
+                *get_id_manger_mut().state_mut::<ButtonState>(id) = ButtonState::default();
+
+                // The manager is unlocked first, the action may well touch state of its own
+                if bounds.contains(*position) {
+                    (self.action)();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::{Mutex, RwLock};
+
+lazy_static::lazy_static! {
+    static ref CLIPBOARD: RwLock<Box<dyn Clipboard>> = RwLock::new(default_clipboard());
+}
+
+/// Where copied text goes, swapped out with `set_clipboard`
+pub trait Clipboard: Send + Sync {
+    fn get_text(&self) -> Option<String>;
+    fn set_text(&self, text: String);
+}
+
+/// A clipboard that only lives as long as the process, for tests and platforms without a system clipboard
+#[derive(Debug, Default)]
+pub struct MemoryClipboard(Mutex<Option<String>>);
+
+impl MemoryClipboard {
+    pub fn new() -> MemoryClipboard {
+        MemoryClipboard::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set_text(&self, text: String) {
+        *self.0.lock().unwrap() = Some(text);
+    }
+}
+
+/// Replaces the clipboard copies go to
+pub fn set_clipboard(clipboard: impl Clipboard + 'static) {
+    *CLIPBOARD.write().unwrap() = Box::new(clipboard);
+}
+
+pub fn get_clipboard_text() -> Option<String> {
+    CLIPBOARD.read().unwrap().get_text()
+}
+
+pub fn set_clipboard_text(text: impl Into<String>) {
+    CLIPBOARD.read().unwrap().set_text(text.into());
+}
+
+#[cfg(target_os = "macos")]
+fn default_clipboard() -> Box<dyn Clipboard> {
+    Box::new(crate::platform::Pasteboard)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn default_clipboard() -> Box<dyn Clipboard> {
+    Box::new(MemoryClipboard::new())
+}
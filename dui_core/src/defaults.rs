@@ -1,2 +1,10 @@
+use std::time::Duration;
 
-pub const DEFAULT_SPACING: f64 = 5.0;
\ No newline at end of file
+/// How far one line of a (non precise) scroll wheel moves, in logical pixels
+pub const DEFAULT_SCROLL_LINE_HEIGHT: f64 = 20.0;
+
+/// Longest gap between clicks that still counts them as a double (or triple) click
+pub const DEFAULT_DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How far apart, in logical pixels, the clicks of a double click can be
+pub const DEFAULT_DOUBLE_CLICK_DISTANCE: f64 = 4.0;
@@ -1,7 +1,14 @@
 use dui_util::Rf;
-use vello::{kurbo::Rect, peniko::Brush, SceneBuilder};
+use vello::{
+    kurbo::{Affine, Rect},
+    SceneBuilder,
+};
 
-use crate::{layout::Id, simple_text::FontManager};
+use crate::{
+    environment::{EnvironmentKey, EnvironmentValues},
+    layout::Id,
+    simple_text::FontManager,
+};
 
 pub struct PathPusher(Rf<Vec<u32>>);
 
@@ -28,10 +35,12 @@ pub struct DrawingContext<'a> {
 
     pub font_manager: Rf<FontManager>,
 
-    pub background_brush: Brush,
-    pub fill_brush: Brush,
-    pub foreground_color: Brush,
+    /// Values inherited from ancestors, modifiers set them on their clone of the context
+    pub environment: EnvironmentValues,
 
+    /// Transform from layout space to the window, composed by each transformed ancestor
+    pub transform: Affine,
+    /// The visible region in window space, narrowed by clipping ancestors
     pub bounding: Rect,
     pub first: bool,
 
@@ -44,6 +53,54 @@ impl DrawingContext<'_> {
         PathPusher::new(self)
     }
 
+    pub fn env<K: EnvironmentKey>(&self) -> K::Value {
+        self.environment.get::<K>()
+    }
+
+    pub fn id(&self) -> Id {
+        self.path.borrow().clone().into()
+    }
+
+    /// Applies `affine` to everything drawn by descendants, on top of the ancestors' transforms
+    pub fn push_transform(&mut self, affine: Affine) {
+        self.transform = self.transform * affine;
+    }
+
+    /// Narrows `bounding` for descendants of a view that clips to `rect` (in layout space)
+    pub fn clip_to(&mut self, rect: Rect) {
+        self.bounding = self.bounding.intersect(self.transform.transform_rect_bbox(rect));
+    }
+
+    /// Whether `rect` (in layout space) is strictly outside of `bounding`, in which case drawing it can be skipped
+    ///
+    /// Empty rects are never clipped, they belong to views without a layout yet or to lines, which still draw a stroke
+    pub fn is_clipped(&self, rect: Rect) -> bool {
+        let rect = self.transform.transform_rect_bbox(rect);
+
+        if rect.width() <= 0.0 || rect.height() <= 0.0 {
+            return false;
+        }
+
+        rect.x1 < self.bounding.x0
+            || rect.x0 > self.bounding.x1
+            || rect.y1 < self.bounding.y0
+            || rect.y0 > self.bounding.y1
+    }
+}
+
+#[derive(Clone)]
+pub struct EventContext {
+    pub path: Rf<Vec<u32>>,
+
+    pub scale_factor: f64,
+}
+
+impl EventContext {
+    pub fn push(&self) -> PathPusher {
+        self.path.borrow_mut().push(0);
+        PathPusher(self.path.clone())
+    }
+
     pub fn id(&self) -> Id {
         self.path.borrow().clone().into()
     }
@@ -73,6 +130,11 @@ pub struct LayoutContext<'a> {
     // pub path: Rc<Vec<u32>>,
     pub path: &'a mut Vec<u32>,
     pub scale_factor: f64,
+    /// Values inherited from ancestors, modifiers restore them once their subtree is laid out
+    pub environment: EnvironmentValues,
+
+    /// Set by views that are animating (e.g. scroll momentum) and need another frame
+    pub needs_redraw: bool,
 }
 
 impl LayoutContext<'_> {
@@ -80,6 +142,10 @@ impl LayoutContext<'_> {
         LayoutPathPusher::new(self)
     }
 
+    pub fn env<K: EnvironmentKey>(&self) -> K::Value {
+        self.environment.get::<K>()
+    }
+
     pub fn id(&self) -> Id {
         self.path.clone().into()
     }
@@ -0,0 +1,166 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+use vello::{
+    kurbo::Rect,
+    peniko::{Brush, Color},
+};
+
+use crate::{
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    event::Event,
+    shape::StrokeStyle,
+    simple_text::TextEnvironment,
+    view::{Element, View},
+};
+
+/// A value inherited down the view tree, read with `env::<Key>()` on the layout and drawing contexts
+///
+/// Keys are types, so values of different keys can't be mixed up even when they have the same type
+pub trait EnvironmentKey: 'static {
+    type Value: Clone + Send + Sync + 'static;
+
+    /// The value seen by views that have no ancestor setting one
+    fn default_value() -> Self::Value;
+}
+
+/// The environment values set by a view's ancestors
+///
+/// Cloning is cheap, values are shared until one is set
+#[derive(Clone, Default)]
+pub struct EnvironmentValues {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl std::fmt::Debug for EnvironmentValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvironmentValues")
+            .field("values", &self.values.len())
+            .finish()
+    }
+}
+
+impl EnvironmentValues {
+    pub fn new() -> EnvironmentValues {
+        EnvironmentValues::default()
+    }
+
+    pub fn get<K: EnvironmentKey>(&self) -> K::Value {
+        self.values
+            .get(&TypeId::of::<K>())
+            .and_then(|value| value.downcast_ref::<K::Value>())
+            .cloned()
+            .unwrap_or_else(K::default_value)
+    }
+
+    pub fn set<K: EnvironmentKey>(&mut self, value: K::Value) {
+        self.values.insert(TypeId::of::<K>(), Arc::new(value));
+    }
+
+    /// Changes the value of `K` in place, starting from the inherited one
+    pub fn update<K: EnvironmentKey>(&mut self, update: impl FnOnce(&mut K::Value)) {
+        let mut value = self.get::<K>();
+        update(&mut value);
+        self.set::<K>(value);
+    }
+}
+
+/// Sets the value of `K` for the view's subtree
+pub struct Environment<V: View, K: EnvironmentKey> {
+    view: V,
+    value: K::Value,
+}
+
+impl<V: View, K: EnvironmentKey> Element for Environment<V, K> {}
+
+impl<V: View, K: EnvironmentKey> View for Environment<V, K> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let mut environment = lctx.environment.clone();
+        environment.set::<K>(self.value.clone());
+
+        let previous = std::mem::replace(&mut lctx.environment, environment);
+        let rect = self.view.layout(lctx, available_rect);
+        lctx.environment = previous;
+
+        rect
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        dctx.environment.set::<K>(self.value.clone());
+
+        self.view.draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait EnvironmentImpl<T: View> {
+    /// Sets `key` to `value` for this view and its descendants, e.g. `.environment(FillBrush, Color::RED.into())`
+    fn environment<K: EnvironmentKey>(self, key: K, value: K::Value) -> Environment<T, K>;
+}
+
+impl<T: View> EnvironmentImpl<T> for T {
+    fn environment<K: EnvironmentKey>(self, _key: K, value: K::Value) -> Environment<T, K> {
+        Environment { view: self, value }
+    }
+}
+
+/// Brush text views fill behind themselves with, set by the `background` modifier
+pub struct BackgroundBrush;
+
+impl EnvironmentKey for BackgroundBrush {
+    type Value = Brush;
+
+    fn default_value() -> Brush {
+        Color::TRANSPARENT.into()
+    }
+}
+
+/// Brush shapes are filled with, set by the `fill` modifier
+pub struct FillBrush;
+
+impl EnvironmentKey for FillBrush {
+    type Value = Brush;
+
+    fn default_value() -> Brush {
+        Color::BLACK.into()
+    }
+}
+
+/// Brush text, unstroked lines and tinted images are drawn with, set by the `foreground` modifier
+pub struct ForegroundColor;
+
+impl EnvironmentKey for ForegroundColor {
+    type Value = Brush;
+
+    fn default_value() -> Brush {
+        Color::BLACK.into()
+    }
+}
+
+/// How shapes are outlined, set by the `stroke` modifier
+pub struct ShapeStroke;
+
+impl EnvironmentKey for ShapeStroke {
+    type Value = Option<StrokeStyle>;
+
+    fn default_value() -> Option<StrokeStyle> {
+        None
+    }
+}
+
+/// Font settings, set by the font and text style modifiers
+pub struct TextSettings;
+
+impl EnvironmentKey for TextSettings {
+    type Value = TextEnvironment;
+
+    fn default_value() -> TextEnvironment {
+        TextEnvironment::default()
+    }
+}
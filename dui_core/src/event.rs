@@ -0,0 +1,126 @@
+use vello::kurbo::{Affine, Point, Vec2};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16),
+}
+
+/// The modifier keys held down when an event happened
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    /// Command on macOS, the Windows key elsewhere
+    pub logo: bool,
+}
+
+impl Modifiers {
+    /// Whether the platform's shortcut modifier is held, command on macOS and control elsewhere
+    pub fn command(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.logo
+        } else {
+            self.control
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// A letter, digit or symbol key, letters are lower case regardless of shift
+    Character(char),
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+}
+
+/// An input event dispatched down the view tree
+///
+/// Positions are in physical pixels relative to the window, the same space layouts are stored in
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// The scroll wheel or trackpad moved by `delta` while the cursor was at `position`
+    ///
+    /// `precise` is set for pixel based deltas (trackpads), which already have momentum applied by the platform
+    MouseWheel {
+        position: Point,
+        delta: Vec2,
+        precise: bool,
+    },
+    /// `click_count` is 2 for a double click, 3 for a triple click and so on
+    MouseDown {
+        position: Point,
+        button: MouseButton,
+        click_count: u32,
+    },
+    MouseUp {
+        position: Point,
+        button: MouseButton,
+    },
+    /// Only sent while a mouse button is held, for dragging
+    MouseMoved {
+        position: Point,
+    },
+    KeyDown {
+        key: Key,
+        modifiers: Modifiers,
+    },
+}
+
+impl Event {
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            Event::MouseWheel { position, .. }
+            | Event::MouseDown { position, .. }
+            | Event::MouseUp { position, .. }
+            | Event::MouseMoved { position } => Some(*position),
+            Event::KeyDown { .. } => None,
+        }
+    }
+
+    /// Maps the event's positions and deltas through `affine`, e.g. into a transformed view's local space
+    pub fn transformed(&self, affine: Affine) -> Event {
+        let linear = |v: Vec2| (affine * v.to_point()) - (affine * Point::ZERO);
+
+        match self {
+            Event::MouseWheel {
+                position,
+                delta,
+                precise,
+            } => Event::MouseWheel {
+                position: affine * *position,
+                delta: linear(*delta),
+                precise: *precise,
+            },
+            Event::MouseDown {
+                position,
+                button,
+                click_count,
+            } => Event::MouseDown {
+                position: affine * *position,
+                button: *button,
+                click_count: *click_count,
+            },
+            Event::MouseUp { position, button } => Event::MouseUp {
+                position: affine * *position,
+                button: *button,
+            },
+            Event::MouseMoved { position } => Event::MouseMoved {
+                position: affine * *position,
+            },
+            Event::KeyDown { .. } => self.clone(),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use image::{imageops::FilterType, RgbaImage};
+use vello::{
+    kurbo::{Affine, Rect, Size},
+    peniko::{self, Blob, Format, Mix},
+};
+
+use crate::{
+    drawing::{DrawingContext, LayoutContext},
+    layout::{get_id_manger, get_id_manger_mut},
+    view::{Element, View},
+};
+
+lazy_static::lazy_static! {
+    static ref IMAGE_CACHE: RwLock<ImageCache> = RwLock::new(ImageCache {
+        decoded: HashMap::new(),
+        resampled: HashMap::new(),
+        resampled_bytes: 0,
+        clock: 0,
+    });
+}
+
+/// How an image is placed in a frame that doesn't match its aspect ratio
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ContentMode {
+    /// Scales to fit entirely inside the frame, keeping the aspect ratio
+    Fit,
+    /// Scales to cover the whole frame, keeping the aspect ratio and clipping the overflow
+    Fill,
+    /// Stretches to the frame, ignoring the aspect ratio
+    Stretch,
+    /// Keeps the natural size, centered and clipped to the frame
+    Center,
+}
+
+/// The filter used when an image is drawn at a size other than its own
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Interpolation {
+    Nearest,
+    Linear,
+    High,
+}
+
+impl Interpolation {
+    fn filter(self) -> FilterType {
+        match self {
+            Interpolation::Nearest => FilterType::Nearest,
+            Interpolation::Linear => FilterType::Triangle,
+            Interpolation::High => FilterType::Lanczos3,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum ImageKey {
+    Path(PathBuf),
+    Static(usize, usize),
+    Hash(u64),
+}
+
+enum ImageSource {
+    Path(PathBuf),
+    Static(&'static [u8]),
+    Bytes(Vec<u8>),
+}
+
+struct DecodedImage {
+    pixels: RgbaImage,
+    image: peniko::Image,
+}
+
+/// An image resampled to one size, remembering when it was last drawn
+struct ResampledImage {
+    image: peniko::Image,
+    last_used: u64,
+}
+
+/// An image, the filter and the size it was resampled with
+type ResampleKey = (ImageKey, Interpolation, u32, u32);
+
+struct ImageCache {
+    decoded: HashMap<ImageKey, io::Result<DecodedImage>>,
+    /// Every size images are drawn at, so redrawing at any of them is free
+    resampled: HashMap<ResampleKey, ResampledImage>,
+    /// Pixel data held by `resampled`, kept under `MAX_RESAMPLED_BYTES`
+    resampled_bytes: usize,
+    /// Bumped on every resample lookup, entries remember when they were last used by it
+    clock: u64,
+}
+
+impl ImageCache {
+    fn decode(&mut self, key: &ImageKey, source: &ImageSource) -> &io::Result<DecodedImage> {
+        self.decoded.entry(key.clone()).or_insert_with(|| {
+            let decoded = match source {
+                ImageSource::Path(path) => image::open(path),
+                ImageSource::Static(bytes) => image::load_from_memory(bytes),
+                ImageSource::Bytes(bytes) => image::load_from_memory(bytes),
+            };
+
+            match decoded {
+                Ok(decoded) => {
+                    let pixels = decoded.to_rgba8();
+                    let image = to_peniko(&pixels);
+
+                    Ok(DecodedImage { pixels, image })
+                }
+                Err(image::ImageError::IoError(err)) => Err(err),
+                Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err.to_string())),
+            }
+        })
+    }
+
+    fn resample(
+        &mut self,
+        key: &ImageKey,
+        interpolation: Interpolation,
+        width: u32,
+        height: u32,
+    ) -> Option<peniko::Image> {
+        let decoded = self.decoded.get(key)?.as_ref().ok()?;
+
+        if (decoded.image.width, decoded.image.height) == (width, height) {
+            return Some(decoded.image.clone());
+        }
+
+        self.clock += 1;
+
+        let cache_key = (key.clone(), interpolation, width, height);
+        if let Some(resampled) = self.resampled.get_mut(&cache_key) {
+            resampled.last_used = self.clock;
+            return Some(resampled.image.clone());
+        }
+
+        let pixels =
+            image::imageops::resize(&decoded.pixels, width, height, interpolation.filter());
+        let image = to_peniko(&pixels);
+
+        let bytes = image.data.len();
+        self.evict(bytes);
+        self.resampled_bytes += bytes;
+        self.resampled.insert(
+            cache_key,
+            ResampledImage {
+                image: image.clone(),
+                last_used: self.clock,
+            },
+        );
+
+        Some(image)
+    }
+
+    /// Drops the least recently used sizes until `incoming` more bytes fit under `MAX_RESAMPLED_BYTES`
+    fn evict(&mut self, incoming: usize) {
+        while self.resampled_bytes + incoming > MAX_RESAMPLED_BYTES {
+            let Some(oldest) = self
+                .resampled
+                .iter()
+                .min_by_key(|(_, resampled)| resampled.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                return;
+            };
+
+            if let Some(resampled) = self.resampled.remove(&oldest) {
+                self.resampled_bytes -= resampled.image.data.len();
+            }
+        }
+    }
+}
+
+fn to_peniko(pixels: &RgbaImage) -> peniko::Image {
+    peniko::Image::new(
+        Blob::new(Arc::new(pixels.as_raw().clone())),
+        Format::Rgba8,
+        pixels.width(),
+        pixels.height(),
+    )
+}
+
+/// Don't resample past this size, vello scales the source instead
+const MAX_RESAMPLE_SIZE: u32 = 4096;
+/// How much resampled pixel data is kept across all images and sizes
+const MAX_RESAMPLED_BYTES: usize = 64 * 1024 * 1024;
+
+/// A bitmap loaded from PNG or JPEG data
+///
+/// Decoding happens the first time the image is laid out and is cached for the lifetime of the program,
+/// keyed by path, by address for static data, or by a hash of the contents for owned data.
+pub struct Image {
+    source: ImageSource,
+    key: ImageKey,
+    size: Option<Size>,
+    content_mode: ContentMode,
+    interpolation: Interpolation,
+    /// Image pixels per logical pixel
+    scale: f64,
+}
+
+impl Image {
+    fn new(source: ImageSource, key: ImageKey, scale: f64) -> Image {
+        Image {
+            source,
+            key,
+            size: None,
+            content_mode: ContentMode::Fit,
+            interpolation: Interpolation::Linear,
+            scale,
+        }
+    }
+
+    /// Loads an image file, `@2x` and `@3x` suffixes on the file name set the image's scale
+    pub fn from_path(path: impl Into<PathBuf>) -> Image {
+        let path = path.into();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let scale = if stem.ends_with("@3x") {
+            3.0
+        } else if stem.ends_with("@2x") {
+            2.0
+        } else {
+            1.0
+        };
+
+        Image::new(ImageSource::Path(path.clone()), ImageKey::Path(path), scale)
+    }
+
+    pub fn from_static(bytes: &'static [u8]) -> Image {
+        Image::new(
+            ImageSource::Static(bytes),
+            ImageKey::Static(bytes.as_ptr() as usize, bytes.len()),
+            1.0,
+        )
+    }
+
+    /// Loads an image from owned bytes, prefer `from_static` or `from_path` as these are hashed every time
+    pub fn from_bytes(bytes: Vec<u8>) -> Image {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let key = ImageKey::Hash(hasher.finish());
+
+        Image::new(ImageSource::Bytes(bytes), key, 1.0)
+    }
+
+    /// Sets the frame size in logical pixels, by default the image's natural size is used
+    pub fn size(self, width: f64, height: f64) -> Image {
+        Image {
+            size: Some(Size::new(width, height)),
+            ..self
+        }
+    }
+
+    pub fn content_mode(self, content_mode: ContentMode) -> Image {
+        Image {
+            content_mode,
+            ..self
+        }
+    }
+
+    pub fn interpolation(self, interpolation: Interpolation) -> Image {
+        Image {
+            interpolation,
+            ..self
+        }
+    }
+
+    /// Sets how many image pixels make up one logical pixel, e.g. 2.0 for @2x assets
+    pub fn scale(self, scale: f64) -> Image {
+        Image { scale, ..self }
+    }
+
+    /// Decodes the image if it isn't cached yet, returning why it can't be drawn
+    ///
+    /// Views that fail to load draw nothing, call this up front to report the error.
+    pub fn load(&self) -> io::Result<()> {
+        match IMAGE_CACHE.write().unwrap().decode(&self.key, &self.source) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+        }
+    }
+
+    /// The natural size of the image in logical pixels
+    fn natural_size(&self) -> Option<Size> {
+        let mut cache = IMAGE_CACHE.write().unwrap();
+        let decoded = cache.decode(&self.key, &self.source).as_ref().ok()?;
+
+        Some(Size::new(
+            decoded.image.width as f64 / self.scale,
+            decoded.image.height as f64 / self.scale,
+        ))
+    }
+
+    /// Where the image goes for the content mode, in the same space as `bounds`
+    fn destination(&self, bounds: Rect, natural: Size) -> Rect {
+        let fit = |scale: f64| {
+            Rect::from_center_size(
+                bounds.center(),
+                (natural.width * scale, natural.height * scale),
+            )
+        };
+
+        match self.content_mode {
+            ContentMode::Stretch => bounds,
+            ContentMode::Center => fit(1.0),
+            ContentMode::Fit => {
+                fit((bounds.width() / natural.width).min(bounds.height() / natural.height))
+            }
+            ContentMode::Fill => {
+                fit((bounds.width() / natural.width).max(bounds.height() / natural.height))
+            }
+        }
+    }
+}
+
+impl Element for Image {}
+
+impl View for Image {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let size = self
+            .size
+            .or_else(|| self.natural_size())
+            .unwrap_or(Size::ZERO);
+
+        let rect = Rect::from_origin_size(
+            available_rect.origin(),
+            (
+                size.width * lctx.scale_factor,
+                size.height * lctx.scale_factor,
+            ),
+        );
+
+        get_id_manger_mut().set_layout_content_rect(lctx.id(), rect);
+
+        rect
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        let bounds = get_id_manger().get_layout(dctx.id()).content_bounds;
+
+        if dctx.is_clipped(bounds) {
+            return;
+        }
+
+        let Some(natural) = self.natural_size() else {
+            return;
+        };
+        let natural = natural * dctx.scale_factor;
+        let destination = self.destination(bounds, natural);
+
+        let width = (destination.width().round() as u32).clamp(1, MAX_RESAMPLE_SIZE);
+        let height = (destination.height().round() as u32).clamp(1, MAX_RESAMPLE_SIZE);
+        let image =
+            IMAGE_CACHE
+                .write()
+                .unwrap()
+                .resample(&self.key, self.interpolation, width, height);
+        let Some(image) = image else { return };
+
+        let transform = dctx.transform
+            * Affine::translate(destination.origin().to_vec2())
+            * Affine::scale_non_uniform(
+                destination.width() / image.width as f64,
+                destination.height() / image.height as f64,
+            );
+
+        let clipped = matches!(self.content_mode, ContentMode::Fill | ContentMode::Center);
+        let mut builder = dctx.builder.borrow_mut();
+
+        if clipped {
+            builder.push_layer(Mix::Clip, 1.0, dctx.transform, &bounds);
+        }
+
+        builder.draw_image(&image, transform);
+
+        if clipped {
+            builder.pop_layer();
+        }
+    }
+}
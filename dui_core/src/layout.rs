@@ -1,13 +1,15 @@
 use std::{
+    any::{Any, TypeId},
     collections::HashMap,
     sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
-use vello::kurbo::Rect;
+use vello::kurbo::{Insets, Rect};
 
 lazy_static::lazy_static! {
     static ref ID_MANAGER: RwLock<IdManager> = RwLock::new(IdManager {
         id_mappings: HashMap::new(),
+        states: HashMap::new(),
     });
 }
 
@@ -26,17 +28,29 @@ pub fn get_id_manger_mut() -> RwLockWriteGuard<'static, IdManager> {
 /// `content_bounds` is the region taken up by only the content
 ///
 /// These are all relative to the screen. If there is no border or padding, they should be equal to the content bounds
+///
+/// `paint_insets` is how far effects like shadows draw outside of the border bounds. The renderer repaints the
+/// whole window every frame, so there are no dirty regions to grow yet, `paint_bounds` is only used for culling
 #[derive(Debug, Default)]
 pub struct Layout {
     pub border_bounds: Rect,
     pub padding_bounds: Rect,
     pub content_bounds: Rect,
+    pub paint_insets: Insets,
+}
+
+impl Layout {
+    /// The region this element can draw to, which is what gets checked when culling
+    pub fn paint_bounds(&self) -> Rect {
+        self.border_bounds + self.paint_insets
+    }
 }
 
 pub const LAYOUT_ZERO: Layout = Layout {
     border_bounds: Rect::ZERO,
     padding_bounds: Rect::ZERO,
     content_bounds: Rect::ZERO,
+    paint_insets: Insets::ZERO,
 };
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq)]
@@ -48,9 +62,19 @@ impl From<Vec<u32>> for Id {
     }
 }
 
-#[derive(Debug)]
 pub struct IdManager {
     pub(crate) id_mappings: HashMap<Id, Layout>,
+    /// Persistent per element state (scroll offsets, etc.), keyed by the owning id and the state type
+    pub(crate) states: HashMap<(Id, TypeId), Box<dyn Any + Send + Sync>>,
+}
+
+impl std::fmt::Debug for IdManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdManager")
+            .field("id_mappings", &self.id_mappings)
+            .field("states", &self.states.len())
+            .finish()
+    }
 }
 
 impl IdManager {
@@ -74,6 +98,7 @@ impl IdManager {
                     padding_bounds: layout,
                     border_bounds: layout,
                     content_bounds: layout,
+                    paint_insets: Insets::ZERO,
                 },
             )
         }
@@ -91,6 +116,7 @@ impl IdManager {
                     padding_bounds: layout,
                     border_bounds: layout,
                     content_bounds: layout,
+                    paint_insets: Insets::ZERO,
                 },
             )
         }
@@ -108,12 +134,49 @@ impl IdManager {
                     padding_bounds: layout,
                     border_bounds: layout,
                     content_bounds: layout,
+                    paint_insets: Insets::ZERO,
                 },
             )
         }
     }
 
+    /// Forgets the paint insets of every layout, called before a layout pass so effects that were removed or
+    /// shrunk don't keep growing the paint bounds
+    pub fn reset_layout_paint_insets(&mut self) {
+        for layout in self.id_mappings.values_mut() {
+            layout.paint_insets = Insets::ZERO;
+        }
+    }
+
+    /// Grows the paint insets so they cover at least `insets` on every side, for views that are laid out more
+    /// than once in a pass
+    pub fn expand_layout_paint_insets(&mut self, id: impl Into<Id>, insets: Insets) {
+        let full = self.id_mappings.entry(id.into()).or_default();
+
+        full.paint_insets = Insets {
+            x0: full.paint_insets.x0.max(insets.x0),
+            y0: full.paint_insets.y0.max(insets.y0),
+            x1: full.paint_insets.x1.max(insets.x1),
+            y1: full.paint_insets.y1.max(insets.y1),
+        };
+    }
+
     pub fn get_layout(&self, id: Id) -> &Layout {
         self.id_mappings.get(&id).unwrap_or(&LAYOUT_ZERO)
     }
+
+    pub fn state<T: Any + Send + Sync>(&self, id: Id) -> Option<&T> {
+        self.states
+            .get(&(id, TypeId::of::<T>()))
+            .and_then(|state| state.downcast_ref())
+    }
+
+    /// Gets the state of type `T` for this id, inserting the default if there is none yet
+    pub fn state_mut<T: Any + Send + Sync + Default>(&mut self, id: Id) -> &mut T {
+        self.states
+            .entry((id, TypeId::of::<T>()))
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut()
+            .unwrap()
+    }
 }
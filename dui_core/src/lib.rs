@@ -1,11 +1,24 @@
 #![feature(return_position_impl_trait_in_trait)]
 #![feature(associated_const_equality)]
 
+pub mod button;
+pub mod clipboard;
 pub mod defaults;
 pub mod drawing;
+pub mod environment;
+pub mod event;
+pub mod image;
 pub mod layout;
+pub mod modifier;
+pub mod paint;
+pub mod rich_text;
+pub mod scroll_view;
+pub mod selection;
+pub mod shape;
 pub mod view;
 pub mod simple_text;
+pub mod svg;
+pub mod theme;
 pub mod platform;
 
 #[derive(Clone, Copy)]
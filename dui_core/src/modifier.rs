@@ -0,0 +1,80 @@
+use vello::kurbo::Rect;
+
+use crate::{
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    event::Event,
+    view::{Element, View},
+};
+
+/// A reusable bundle of modifiers, applied to a view with `.modifier(..)`
+///
+/// ```ignore
+/// struct CardStyle;
+///
+/// impl ViewModifier for CardStyle {
+///     fn body<V: View>(&self, content: ModifierContent<V>) -> impl View {
+///         content.padding(5.0).background(Color::WHITE).border(1.0, Color::GRAY)
+///     }
+/// }
+/// ```
+pub trait ViewModifier {
+    fn body<V: View>(&self, content: ModifierContent<V>) -> impl View;
+}
+
+/// The view a `ViewModifier` is applied to, as handed to its `body`
+pub struct ModifierContent<'a, V: View>(&'a V);
+
+impl<V: View> Element for ModifierContent<'_, V> {}
+
+impl<V: View> View for ModifierContent<'_, V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.0.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        self.0.draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.0.event(ectx, event)
+    }
+}
+
+/// A view with a `ViewModifier` applied, the modifier's body is built again for every pass like any other body
+pub struct Modified<V: View, M: ViewModifier> {
+    view: V,
+    modifier: M,
+}
+
+impl<V: View, M: ViewModifier> Element for Modified<V, M> {}
+
+impl<V: View, M: ViewModifier> View for Modified<V, M> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.modifier
+            .body(ModifierContent(&self.view))
+            .layout(lctx, available_rect)
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        self.modifier.body(ModifierContent(&self.view)).draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.modifier
+            .body(ModifierContent(&self.view))
+            .event(ectx, event)
+    }
+}
+
+pub trait ModifierImpl<T: View> {
+    fn modifier<M: ViewModifier>(self, modifier: M) -> Modified<T, M>;
+}
+
+impl<T: View> ModifierImpl<T> for T {
+    fn modifier<M: ViewModifier>(self, modifier: M) -> Modified<T, M> {
+        Modified {
+            view: self,
+            modifier,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use vello::{
+    kurbo::{Point, Rect},
+    peniko::{Brush, Color, ColorStops, ColorStopsSource, Extend, Gradient},
+};
+
+/// A point in a view's unit space, where (0, 0) is the top leading and (1, 1) the bottom trailing corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnitPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl UnitPoint {
+    pub const TOP_LEADING: UnitPoint = UnitPoint::new(0.0, 0.0);
+    pub const TOP: UnitPoint = UnitPoint::new(0.5, 0.0);
+    pub const TOP_TRAILING: UnitPoint = UnitPoint::new(1.0, 0.0);
+    pub const LEADING: UnitPoint = UnitPoint::new(0.0, 0.5);
+    pub const CENTER: UnitPoint = UnitPoint::new(0.5, 0.5);
+    pub const TRAILING: UnitPoint = UnitPoint::new(1.0, 0.5);
+    pub const BOTTOM_LEADING: UnitPoint = UnitPoint::new(0.0, 1.0);
+    pub const BOTTOM: UnitPoint = UnitPoint::new(0.5, 1.0);
+    pub const BOTTOM_TRAILING: UnitPoint = UnitPoint::new(1.0, 1.0);
+
+    pub const fn new(x: f64, y: f64) -> UnitPoint {
+        UnitPoint { x, y }
+    }
+
+    pub fn resolve(self, bounds: Rect) -> Point {
+        Point::new(
+            bounds.x0 + self.x * bounds.width(),
+            bounds.y0 + self.y * bounds.height(),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum UnitGradientKind {
+    Linear {
+        start: UnitPoint,
+        end: UnitPoint,
+    },
+    /// `radius` is relative to the larger side of the bounds
+    Radial {
+        center: UnitPoint,
+        radius: f64,
+    },
+    /// Angles are in radians
+    Sweep {
+        center: UnitPoint,
+        start_angle: f32,
+        end_angle: f32,
+    },
+}
+
+/// A gradient whose geometry is given in unit space, so it stretches with the view it's drawn in
+#[derive(Clone, Debug)]
+pub struct UnitGradient {
+    kind: UnitGradientKind,
+    stops: ColorStops,
+    extend: Extend,
+}
+
+impl UnitGradient {
+    fn new(kind: UnitGradientKind) -> UnitGradient {
+        UnitGradient {
+            kind,
+            stops: ColorStops::new(),
+            extend: Extend::Pad,
+        }
+    }
+
+    pub fn linear(start: UnitPoint, end: UnitPoint) -> UnitGradient {
+        UnitGradient::new(UnitGradientKind::Linear { start, end })
+    }
+
+    pub fn radial(center: UnitPoint, radius: f64) -> UnitGradient {
+        UnitGradient::new(UnitGradientKind::Radial { center, radius })
+    }
+
+    pub fn sweep(center: UnitPoint, start_angle: f32, end_angle: f32) -> UnitGradient {
+        UnitGradient::new(UnitGradientKind::Sweep {
+            center,
+            start_angle,
+            end_angle,
+        })
+    }
+
+    /// Sets the color stops, e.g. `&[Color::RED, Color::BLUE]` or `&[(0.0, Color::RED), (1.0, Color::BLUE)]`
+    pub fn stops(self, stops: impl ColorStopsSource) -> UnitGradient {
+        let mut collected = ColorStops::new();
+        stops.collect_stops(&mut collected);
+
+        UnitGradient {
+            stops: collected,
+            ..self
+        }
+    }
+
+    pub fn extend(self, extend: Extend) -> UnitGradient {
+        UnitGradient { extend, ..self }
+    }
+
+    /// Places the gradient in `bounds`, producing a brush in the same space as the layouts
+    pub fn resolve(&self, bounds: Rect) -> Gradient {
+        let mut gradient = match self.kind {
+            UnitGradientKind::Linear { start, end } => {
+                Gradient::new_linear(start.resolve(bounds), end.resolve(bounds))
+            }
+            UnitGradientKind::Radial { center, radius } => Gradient::new_radial(
+                center.resolve(bounds),
+                (radius * bounds.width().max(bounds.height())) as f32,
+            ),
+            UnitGradientKind::Sweep {
+                center,
+                start_angle,
+                end_angle,
+            } => Gradient::new_sweep(center.resolve(bounds), start_angle, end_angle),
+        };
+
+        gradient.stops = self.stops.clone();
+        gradient.extend = self.extend;
+        gradient
+    }
+}
+
+/// Anything views can be painted with, brushes are used as is and unit gradients are fit to the view
+#[derive(Clone, Debug)]
+pub enum Paint {
+    Brush(Brush),
+    Gradient(UnitGradient),
+}
+
+impl Paint {
+    pub fn resolve(&self, bounds: Rect) -> Brush {
+        match self {
+            Paint::Brush(brush) => brush.clone(),
+            Paint::Gradient(gradient) => Brush::Gradient(gradient.resolve(bounds)),
+        }
+    }
+}
+
+impl From<Brush> for Paint {
+    fn from(brush: Brush) -> Self {
+        Paint::Brush(brush)
+    }
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Self {
+        Paint::Brush(Brush::Solid(color))
+    }
+}
+
+impl From<Gradient> for Paint {
+    fn from(gradient: Gradient) -> Self {
+        Paint::Brush(Brush::Gradient(gradient))
+    }
+}
+
+impl From<UnitGradient> for Paint {
+    fn from(gradient: UnitGradient) -> Self {
+        Paint::Gradient(gradient)
+    }
+}
@@ -3,6 +3,7 @@ use cocoa::{
         NSView, NSViewHeightSizable, NSViewWidthSizable, NSVisualEffectBlendingMode,
         NSVisualEffectMaterial, NSVisualEffectState, NSVisualEffectView, NSWindow,
         NSWindowOrderingMode, NSApplicationActivationOptions, NSColor, NSWindowStyleMask,
+        NSPasteboardTypeString,
     },
     base::{nil, id},
     foundation::NSAutoreleasePool,
@@ -119,3 +120,36 @@ pub fn get_color() -> Color {
         Color::rgb(red, green, blue)
     }
 }
+
+/// The general system pasteboard
+pub struct Pasteboard;
+
+impl crate::clipboard::Clipboard for Pasteboard {
+    fn get_text(&self) -> Option<String> {
+        unsafe {
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let string: id = msg_send![pasteboard, stringForType: NSPasteboardTypeString];
+            if string == nil {
+                return None;
+            }
+
+            let bytes: *const std::os::raw::c_char = msg_send![string, UTF8String];
+            Some(
+                std::ffi::CStr::from_ptr(bytes)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    fn set_text(&self, text: String) {
+        unsafe {
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let string = str_to_nsstring(&text);
+
+            let _: i64 = msg_send![pasteboard, clearContents];
+            let _: BOOL = msg_send![pasteboard, setString: string forType: NSPasteboardTypeString];
+            let _: () = msg_send![string, release];
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::ops::Range;
+
+use vello::{
+    kurbo::{Point, Rect},
+    peniko::{Brush, Color},
+};
+
+use crate::{
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    environment::{BackgroundBrush, ForegroundColor, TextSettings},
+    event::{Event, MouseButton},
+    layout::{get_id_manger, get_id_manger_mut},
+    simple_text::{FontWeight, TextDecoration, TextEnvironment, TextLayout},
+    theme::Theme,
+    view::{Element, View},
+};
+
+/// Font and paint attributes of a span of an `AttributedString`, the unset ones come from the text environment
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextAttributes {
+    pub family: Option<String>,
+    pub size: Option<f32>,
+    pub weight: Option<FontWeight>,
+    pub width: Option<f32>,
+    pub italic: Option<bool>,
+    pub underline: Option<TextDecoration>,
+    pub strikethrough: Option<TextDecoration>,
+    pub highlight: Option<Color>,
+    pub brush: Option<Brush>,
+    pub link: Option<String>,
+}
+
+impl TextAttributes {
+    pub fn new() -> TextAttributes {
+        TextAttributes::default()
+    }
+
+    pub fn font(self, family: impl Into<String>) -> TextAttributes {
+        TextAttributes {
+            family: Some(family.into()),
+            ..self
+        }
+    }
+
+    pub fn size(self, size: f32) -> TextAttributes {
+        TextAttributes {
+            size: Some(size),
+            ..self
+        }
+    }
+
+    pub fn weight(self, weight: FontWeight) -> TextAttributes {
+        TextAttributes {
+            weight: Some(weight),
+            ..self
+        }
+    }
+
+    pub fn bold(self) -> TextAttributes {
+        self.weight(FontWeight::BOLD)
+    }
+
+    pub fn width(self, width: f32) -> TextAttributes {
+        TextAttributes {
+            width: Some(width),
+            ..self
+        }
+    }
+
+    pub fn italic(self) -> TextAttributes {
+        TextAttributes {
+            italic: Some(true),
+            ..self
+        }
+    }
+
+    pub fn underline(self, decoration: TextDecoration) -> TextAttributes {
+        TextAttributes {
+            underline: Some(decoration),
+            ..self
+        }
+    }
+
+    pub fn strikethrough(self, decoration: TextDecoration) -> TextAttributes {
+        TextAttributes {
+            strikethrough: Some(decoration),
+            ..self
+        }
+    }
+
+    pub fn highlight(self, color: Color) -> TextAttributes {
+        TextAttributes {
+            highlight: Some(color),
+            ..self
+        }
+    }
+
+    pub fn brush(self, brush: impl Into<Brush>) -> TextAttributes {
+        TextAttributes {
+            brush: Some(brush.into()),
+            ..self
+        }
+    }
+
+    /// Makes the span a link, clicking it calls the `on_link` action of the `RichText` it's shown in
+    pub fn link(self, url: impl Into<String>) -> TextAttributes {
+        TextAttributes {
+            link: Some(url.into()),
+            ..self
+        }
+    }
+
+    fn resolve(&self, text_env: &TextEnvironment) -> TextEnvironment {
+        TextEnvironment {
+            family: self.family.clone().or_else(|| text_env.family.clone()),
+            size: self.size.unwrap_or(text_env.size),
+            weight: self.weight.unwrap_or(text_env.weight),
+            width: self.width.unwrap_or(text_env.width),
+            italic: self.italic.unwrap_or(text_env.italic),
+            underline: self.underline.or(text_env.underline),
+            strikethrough: self.strikethrough.or(text_env.strikethrough),
+            highlight: self.highlight.or(text_env.highlight),
+            ..text_env.clone()
+        }
+    }
+}
+
+/// A string made of spans that each have their own `TextAttributes`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttributedString {
+    text: String,
+    spans: Vec<(Range<usize>, TextAttributes)>,
+}
+
+impl AttributedString {
+    pub fn new() -> AttributedString {
+        AttributedString::default()
+    }
+
+    /// Appends `text` as a span with `attributes`
+    pub fn push(mut self, text: impl AsRef<str>, attributes: TextAttributes) -> AttributedString {
+        let text = text.as_ref();
+        if text.is_empty() {
+            return self;
+        }
+
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push((start..self.text.len(), attributes));
+
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn spans(&self) -> impl Iterator<Item = (&str, &TextAttributes)> {
+        self.spans
+            .iter()
+            .map(|(range, attributes)| (&self.text[range.clone()], attributes))
+    }
+
+    /// The link of the span containing the byte at `index`, if it's a link
+    pub fn link_at(&self, index: usize) -> Option<&str> {
+        self.spans
+            .iter()
+            .find(|(range, _)| range.contains(&index))
+            .and_then(|(_, attributes)| attributes.link.as_deref())
+    }
+
+    /// The environment of each span, at least one so empty strings still get a line
+    fn styles(&self, text_env: &TextEnvironment) -> Vec<(Range<usize>, TextEnvironment)> {
+        if self.spans.is_empty() {
+            return vec![(0..self.text.len(), text_env.clone())];
+        }
+
+        self.spans
+            .iter()
+            .map(|(range, attributes)| (range.clone(), attributes.resolve(text_env)))
+            .collect()
+    }
+}
+
+impl From<&str> for AttributedString {
+    fn from(text: &str) -> Self {
+        AttributedString::new().push(text, TextAttributes::default())
+    }
+}
+
+impl From<String> for AttributedString {
+    fn from(text: String) -> Self {
+        AttributedString::from(text.as_str())
+    }
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct LinkState {
+    /// The range of the link a press started on, it's only followed if released over the same link
+    pressed: Option<Range<usize>>,
+}
+
+/// Text made of spans in different fonts and brushes, laid out as a single paragraph
+pub struct RichText {
+    text: AttributedString,
+    on_link: Option<Box<dyn Fn(&str)>>,
+}
+
+impl RichText {
+    pub fn new(text: impl Into<AttributedString>) -> RichText {
+        RichText {
+            text: text.into(),
+            on_link: None,
+        }
+    }
+
+    /// Calls `action` with the url of a link span when it's clicked
+    pub fn on_link(self, action: impl Fn(&str) + 'static) -> RichText {
+        RichText {
+            on_link: Some(Box::new(action)),
+            ..self
+        }
+    }
+
+    /// The range and url of the link drawn under `position`, in window space
+    fn link_under(
+        &self,
+        layout: &TextLayout,
+        bounds: Rect,
+        position: Point,
+    ) -> Option<(Range<usize>, &str)> {
+        let point = position - bounds.origin().to_vec2();
+
+        self.text
+            .spans
+            .iter()
+            .find(|(range, attributes)| {
+                attributes.link.is_some()
+                    && layout
+                        .selection_rects(range.clone())
+                        .iter()
+                        .any(|rect| rect.contains(point))
+            })
+            .and_then(|(range, _)| Some((range.clone(), self.text.link_at(range.start)?)))
+    }
+}
+
+impl Element for RichText {}
+
+impl View for RichText {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let id = lctx.id();
+        let width = available_rect.width();
+        let scale = lctx.scale_factor as f32;
+        let text_env = lctx.env::<TextSettings>();
+        let styles = self.text.styles(&text_env);
+
+        let valid = get_id_manger()
+            .state::<TextLayout>(id.clone())
+            .map(|layout| {
+                layout.is_valid_for_spans(&self.text.text, &text_env, &styles, width, scale)
+            })
+            .unwrap_or(false);
+
+        let layout = (!valid).then(|| {
+            lctx.font_manager.borrow().layout_spans(
+                &text_env,
+                &styles,
+                scale,
+                width,
+                &self.text.text,
+            )
+        });
+
+        let mut manager = get_id_manger_mut();
+        if let Some(layout) = layout {
+            *manager.state_mut::<TextLayout>(id.clone()) = layout;
+        }
+
+        let size = manager.state_mut::<TextLayout>(id.clone()).size;
+        let rect = Rect::from_origin_size(available_rect.origin(), size);
+        manager.set_layout_content_rect(id, rect);
+
+        rect
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        let binding = get_id_manger();
+        let rect = binding.get_layout(dctx.id());
+
+        if dctx.is_clipped(rect.content_bounds) {
+            return;
+        }
+
+        dctx.builder.borrow_mut().fill(
+            vello::peniko::Fill::NonZero,
+            dctx.transform,
+            &dctx.env::<BackgroundBrush>(),
+            None,
+            &rect.content_bounds,
+        );
+
+        let Some(layout) = binding.state::<TextLayout>(dctx.id()) else {
+            return;
+        };
+
+        let foreground = dctx.env::<ForegroundColor>();
+        // Links that don't set a brush of their own are drawn in the accent color
+        let link = Brush::Solid(dctx.env::<Theme>().colors.accent);
+        let brushes = self
+            .text
+            .spans
+            .iter()
+            .map(
+                |(_, attributes)| match (&attributes.brush, &attributes.link) {
+                    (Some(brush), _) => brush,
+                    (None, Some(_)) => &link,
+                    (None, None) => &foreground,
+                },
+            )
+            .collect::<Vec<_>>();
+        let brushes = if brushes.is_empty() {
+            vec![&foreground]
+        } else {
+            brushes
+        };
+
+        dctx.font_manager.borrow_mut().draw_layout(
+            &mut dctx.builder.borrow_mut(),
+            layout,
+            &brushes,
+            dctx.transform,
+            rect.content_bounds.origin(),
+        );
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let Some(on_link) = &self.on_link else {
+            return false;
+        };
+        let id = ectx.id();
+
+        let link_under = |position: Point| {
+            let manager = get_id_manger();
+            let bounds = manager.get_layout(id.clone()).content_bounds;
+
+            manager
+                .state::<TextLayout>(id.clone())
+                .and_then(|layout| self.link_under(layout, bounds, position))
+        };
+
+        match event {
+            Event::MouseDown {
+                position,
+                button: MouseButton::Left,
+                click_count: 1,
+            } => {
+                let Some((range, _)) = link_under(*position) else {
+                    return false;
+                };
+
+                get_id_manger_mut()
+                    .state_mut::<LinkState>(id.clone())
+                    .pressed = Some(range);
+                true
+            }
+            Event::MouseUp {
+                position,
+                button: MouseButton::Left,
+            } => {
+                let Some(pressed) = get_id_manger_mut()
+                    .state_mut::<LinkState>(id.clone())
+                    .pressed
+                    .take()
+                else {
+                    return false;
+                };
+
+                // The manager is unlocked first, the action may well touch state of its own
+                if let Some((_, url)) = link_under(*position).filter(|(range, _)| *range == pressed)
+                {
+                    on_link(url);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
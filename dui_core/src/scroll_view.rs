@@ -0,0 +1,338 @@
+use std::time::Instant;
+
+use vello::{
+    kurbo::{Rect, RoundedRect, Size, Vec2},
+    peniko::{Brush, Mix},
+};
+
+use crate::{
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    event::Event,
+    layout::{get_id_manger, get_id_manger_mut},
+    theme::Theme,
+    view::{Element, View},
+};
+
+/// How much of a wheel delta is carried over into momentum, per second
+const MOMENTUM_GAIN: f64 = 8.0;
+/// Exponential decay rate of the momentum velocity, per second
+const MOMENTUM_DECAY: f64 = 6.0;
+/// Velocity (pixels per second) below which momentum stops
+const MOMENTUM_MIN_VELOCITY: f64 = 5.0;
+
+const INDICATOR_WIDTH: f64 = 6.0;
+const INDICATOR_INSET: f64 = 2.0;
+const INDICATOR_MIN_LENGTH: f64 = 20.0;
+/// Opacity of the theme's text color the indicators are drawn in
+const INDICATOR_OPACITY: f32 = 0.4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ScrollAxes {
+    Vertical,
+    Horizontal,
+    Both,
+}
+
+impl ScrollAxes {
+    pub fn horizontal(self) -> bool {
+        matches!(self, ScrollAxes::Horizontal | ScrollAxes::Both)
+    }
+
+    pub fn vertical(self) -> bool {
+        matches!(self, ScrollAxes::Vertical | ScrollAxes::Both)
+    }
+
+    /// Zeroes out the components of `delta` that can't be scrolled
+    fn mask(self, delta: Vec2) -> Vec2 {
+        Vec2 {
+            x: if self.horizontal() { delta.x } else { 0.0 },
+            y: if self.vertical() { delta.y } else { 0.0 },
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub(crate) struct ScrollState {
+    offset: Vec2,
+    velocity: Vec2,
+    content_size: Size,
+    last_tick: Option<Instant>,
+}
+
+impl ScrollState {
+    /// Advances the momentum since the last tick, returning `true` if still moving
+    fn tick(&mut self) -> bool {
+        let now = Instant::now();
+        let dt = self
+            .last_tick
+            .map(|last| (now - last).as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_tick = Some(now);
+
+        if self.velocity.hypot() < MOMENTUM_MIN_VELOCITY {
+            self.velocity = Vec2::ZERO;
+            return false;
+        }
+
+        self.offset += self.velocity * dt;
+        self.velocity *= (-MOMENTUM_DECAY * dt).exp();
+
+        true
+    }
+
+    fn clamp(&mut self, viewport: Size) {
+        let max_x = (self.content_size.width - viewport.width).max(0.0);
+        let max_y = (self.content_size.height - viewport.height).max(0.0);
+
+        let x = self.offset.x.clamp(0.0, max_x);
+        let y = self.offset.y.clamp(0.0, max_y);
+
+        // Hitting an edge stops any momentum on that axis
+        if x != self.offset.x {
+            self.velocity.x = 0.0;
+        }
+        if y != self.offset.y {
+            self.velocity.y = 0.0;
+        }
+
+        self.offset = Vec2::new(x, y);
+    }
+}
+
+/// A container that lays its content out unbounded along `axes` and shows a scrollable window of it
+pub struct ScrollView<V: View> {
+    view: V,
+    axes: ScrollAxes,
+    momentum: bool,
+    show_indicators: bool,
+}
+
+impl<V: View> ScrollView<V> {
+    pub fn new(view: V) -> ScrollView<V> {
+        ScrollView {
+            view,
+            axes: ScrollAxes::Vertical,
+            momentum: true,
+            show_indicators: true,
+        }
+    }
+
+    pub fn axes(self, axes: ScrollAxes) -> ScrollView<V> {
+        ScrollView { axes, ..self }
+    }
+
+    pub fn momentum(self, momentum: bool) -> ScrollView<V> {
+        ScrollView { momentum, ..self }
+    }
+
+    pub fn indicators(self, show_indicators: bool) -> ScrollView<V> {
+        ScrollView {
+            show_indicators,
+            ..self
+        }
+    }
+
+    fn layout_content(&self, lctx: &mut LayoutContext, available_rect: Rect, offset: Vec2) -> Rect {
+        let content_rect = Rect {
+            x0: available_rect.x0 - offset.x,
+            y0: available_rect.y0 - offset.y,
+            x1: if self.axes.horizontal() {
+                f64::INFINITY
+            } else {
+                available_rect.x1
+            },
+            y1: if self.axes.vertical() {
+                f64::INFINITY
+            } else {
+                available_rect.y1
+            },
+        };
+
+        lctx.path.push(0);
+        let layout = self.view.layout(lctx, content_rect);
+        lctx.path.pop();
+
+        layout
+    }
+
+    fn draw_indicators(&self, dctx: &DrawingContext, bounds: Rect, state: &ScrollState) {
+        let width = INDICATOR_WIDTH * dctx.scale_factor;
+        let inset = INDICATOR_INSET * dctx.scale_factor;
+        let min_length = INDICATOR_MIN_LENGTH * dctx.scale_factor;
+        let brush = Brush::Solid(
+            dctx.env::<Theme>()
+                .colors
+                .text
+                .with_alpha_factor(INDICATOR_OPACITY),
+        );
+
+        let mut thumbs = Vec::with_capacity(2);
+
+        if self.axes.vertical() && state.content_size.height > bounds.height() {
+            let track = bounds.height() - inset * 2.0;
+            let length = (track * bounds.height() / state.content_size.height).max(min_length);
+            let progress = state.offset.y / (state.content_size.height - bounds.height());
+            let y0 = bounds.y0 + inset + (track - length) * progress;
+
+            thumbs.push(Rect::new(
+                bounds.x1 - inset - width,
+                y0,
+                bounds.x1 - inset,
+                y0 + length,
+            ));
+        }
+
+        if self.axes.horizontal() && state.content_size.width > bounds.width() {
+            let track = bounds.width() - inset * 2.0;
+            let length = (track * bounds.width() / state.content_size.width).max(min_length);
+            let progress = state.offset.x / (state.content_size.width - bounds.width());
+            let x0 = bounds.x0 + inset + (track - length) * progress;
+
+            thumbs.push(Rect::new(
+                x0,
+                bounds.y1 - inset - width,
+                x0 + length,
+                bounds.y1 - inset,
+            ));
+        }
+
+        let mut builder = dctx.builder.borrow_mut();
+        for thumb in thumbs {
+            builder.fill(
+                vello::peniko::Fill::NonZero,
+                dctx.transform,
+                &brush,
+                None,
+                &RoundedRect::from_rect(thumb, width / 2.0),
+            );
+        }
+    }
+}
+
+impl<V: View> Element for ScrollView<V> {}
+
+impl<V: View> View for ScrollView<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let id = lctx.id();
+
+        let (offset, moving) = {
+            let mut manager = get_id_manger_mut();
+            let state = manager.state_mut::<ScrollState>(id.clone());
+            let moving = self.momentum && state.tick();
+
+            (state.offset, moving)
+        };
+
+        let content = self.layout_content(lctx, available_rect, offset);
+
+        let used_rect = Rect {
+            x1: available_rect.x0
+                + if self.axes.horizontal() {
+                    content.width().min(available_rect.width())
+                } else {
+                    content.width()
+                },
+            y1: available_rect.y0
+                + if self.axes.vertical() {
+                    content.height().min(available_rect.height())
+                } else {
+                    content.height()
+                },
+            ..available_rect
+        };
+
+        let clamped = {
+            let mut manager = get_id_manger_mut();
+            let state = manager.state_mut::<ScrollState>(id.clone());
+            state.content_size = content.size();
+            state.clamp(used_rect.size());
+
+            state.offset
+        };
+
+        // The content size changed underneath the offset (e.g. a resize), so place the children again
+        if clamped != offset {
+            self.layout_content(lctx, available_rect, clamped);
+        }
+
+        if moving {
+            lctx.needs_redraw = true;
+        }
+
+        get_id_manger_mut().set_layout_content_rect(id, used_rect);
+
+        used_rect
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let (bounds, state) = {
+            let binding = get_id_manger();
+            let bounds = binding.get_layout(dctx.id()).content_bounds;
+            let state = binding
+                .state::<ScrollState>(dctx.id())
+                .copied()
+                .unwrap_or_default();
+
+            (bounds, state)
+        };
+
+        dctx.clip_to(bounds);
+
+        dctx.builder
+            .borrow_mut()
+            .push_layer(Mix::Clip, 1.0, dctx.transform, &bounds);
+
+        {
+            let _pushed = dctx.push();
+
+            self.view.draw(dctx.clone());
+        }
+
+        if self.show_indicators {
+            self.draw_indicators(&dctx, bounds, &state);
+        }
+
+        dctx.builder.borrow_mut().pop_layer();
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        {
+            let _pushed = ectx.push();
+
+            // Give nested scroll views the first chance at the event
+            if self.view.event(ectx.clone(), event) {
+                return true;
+            }
+        }
+
+        match event {
+            Event::MouseWheel {
+                position,
+                delta,
+                precise,
+            } => {
+                let id = ectx.id();
+                let mut manager = get_id_manger_mut();
+                let bounds = manager.get_layout(id.clone()).content_bounds;
+
+                if !bounds.contains(*position) {
+                    return false;
+                }
+
+                let delta = self.axes.mask(*delta);
+                let state = manager.state_mut::<ScrollState>(id);
+                let before = state.offset;
+
+                state.offset -= delta;
+                if self.momentum && !precise {
+                    state.velocity -= delta * MOMENTUM_GAIN;
+                    state.last_tick = Some(Instant::now());
+                }
+                state.clamp(bounds.size());
+
+                state.offset != before
+            }
+            _ => false,
+        }
+    }
+}
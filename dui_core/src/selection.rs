@@ -0,0 +1,354 @@
+use std::ops::Range;
+use std::sync::RwLock;
+
+use vello::{kurbo::Rect, peniko::Brush};
+
+use crate::{
+    clipboard::set_clipboard_text,
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    event::{Event, Key, MouseButton},
+    layout::{get_id_manger, get_id_manger_mut, Id},
+    rich_text::RichText,
+    simple_text::TextLayout,
+    theme::Theme,
+    view::{Element, Text, View},
+};
+
+/// Opacity of the theme's accent color behind selected text
+pub const SELECTION_OPACITY: f32 = 0.3;
+
+lazy_static::lazy_static! {
+    /// The text holding the selection, there is only ever one selection in the window
+    static ref SELECTED: RwLock<Option<Id>> = RwLock::new(None);
+}
+
+#[derive(Default, Clone, Copy)]
+pub(crate) struct SelectionState {
+    /// Where the selection started, as a byte offset into the text
+    anchor: usize,
+    /// Where the selection ends, which moves while dragging
+    focus: usize,
+    dragging: bool,
+}
+
+impl SelectionState {
+    fn range(&self) -> Range<usize> {
+        self.anchor.min(self.focus)..self.anchor.max(self.focus)
+    }
+}
+
+/// Lets the text inside be selected with the mouse and copied to the clipboard
+///
+/// Dragging selects a range, a double click selects a word and a triple click a line
+pub struct Selectable<V: View> {
+    view: V,
+}
+
+impl<V: View> Element for Selectable<V> {}
+
+impl<V: View> View for Selectable<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        // Shares its id with the text, which is where the text layout is stored
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        let id = dctx.id();
+        self.view.draw(dctx.clone());
+
+        if SELECTED.read().unwrap().as_ref() != Some(&id) {
+            return;
+        }
+
+        let binding = get_id_manger();
+        let bounds = binding.get_layout(id.clone()).content_bounds;
+        let (Some(layout), Some(state)) = (
+            binding.state::<TextLayout>(id.clone()),
+            binding.state::<SelectionState>(id),
+        ) else {
+            return;
+        };
+
+        let brush = Brush::Solid(
+            dctx.env::<Theme>()
+                .colors
+                .accent
+                .with_alpha_factor(SELECTION_OPACITY),
+        );
+        let mut builder = dctx.builder.borrow_mut();
+
+        for rect in layout.selection_rects(state.range()) {
+            builder.fill(
+                vello::peniko::Fill::NonZero,
+                dctx.transform,
+                &brush,
+                None,
+                &(rect + bounds.origin().to_vec2()),
+            );
+        }
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        if self.view.event(ectx.clone(), event) {
+            return true;
+        }
+
+        let id = ectx.id();
+        let selected = SELECTED.read().unwrap().as_ref() == Some(&id);
+
+        match event {
+            Event::MouseDown {
+                position,
+                button: MouseButton::Left,
+                click_count,
+            } => {
+                let mut manager = get_id_manger_mut();
+                let bounds = manager.get_layout(id.clone()).content_bounds;
+
+                if !bounds.contains(*position) {
+                    // Clicking anywhere else drops the selection, but the click is still someone else's
+                    if selected {
+                        *SELECTED.write().unwrap() = None;
+                    }
+                    return false;
+                }
+
+                let Some(layout) = manager.state::<TextLayout>(id.clone()) else {
+                    return false;
+                };
+
+                let index = layout.index_at(*position - bounds.origin().to_vec2());
+                let range = match click_count {
+                    0 | 1 => index..index,
+                    2 => layout.word_range(index),
+                    _ => layout.line_range(index),
+                };
+
+                *manager.state_mut::<SelectionState>(id.clone()) = SelectionState {
+                    anchor: range.start,
+                    focus: range.end,
+                    dragging: *click_count <= 1,
+                };
+                *SELECTED.write().unwrap() = Some(id);
+
+                true
+            }
+            Event::MouseMoved { position } if selected => {
+                let mut manager = get_id_manger_mut();
+                let bounds = manager.get_layout(id.clone()).content_bounds;
+
+                let Some(layout) = manager.state::<TextLayout>(id.clone()) else {
+                    return false;
+                };
+                let index = layout.index_at(*position - bounds.origin().to_vec2());
+
+                let state = manager.state_mut::<SelectionState>(id);
+                if !state.dragging || state.focus == index {
+                    return false;
+                }
+
+                state.focus = index;
+                true
+            }
+            Event::MouseUp {
+                button: MouseButton::Left,
+                ..
+            } if selected => {
+                get_id_manger_mut().state_mut::<SelectionState>(id).dragging = false;
+                false
+            }
+            Event::KeyDown {
+                key: Key::Character('c'),
+                modifiers,
+            } if selected && modifiers.command() => {
+                let manager = get_id_manger();
+                let (Some(layout), Some(state)) = (
+                    manager.state::<TextLayout>(id.clone()),
+                    manager.state::<SelectionState>(id),
+                ) else {
+                    return false;
+                };
+
+                match layout.text().get(state.range()) {
+                    Some(text) if !text.is_empty() => {
+                        set_clipboard_text(text);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Event::KeyDown {
+                key: Key::Character('a'),
+                modifiers,
+            } if selected && modifiers.command() => {
+                let mut manager = get_id_manger_mut();
+                let Some(len) = manager
+                    .state::<TextLayout>(id.clone())
+                    .map(|layout| layout.text().len())
+                else {
+                    return false;
+                };
+
+                *manager.state_mut::<SelectionState>(id) = SelectionState {
+                    anchor: 0,
+                    focus: len,
+                    dragging: false,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Only text views can be made selectable, since the selection is found through their `TextLayout`
+pub trait SelectableImpl: View + Sized {
+    fn selectable(self) -> Selectable<Self> {
+        Selectable { view: self }
+    }
+}
+
+impl SelectableImpl for Text {}
+
+impl SelectableImpl for RichText {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use dui_util::Rf;
+    use vello::kurbo::Point;
+
+    use super::*;
+    use crate::{
+        clipboard::{get_clipboard_text, set_clipboard, MemoryClipboard},
+        environment::EnvironmentValues,
+        event::Modifiers,
+        simple_text::FontManager,
+    };
+
+    /// The selection, clipboard and id manager are global, so tests that click run one at a time
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    const TEXT: &str = "Hello brave world\nSecond line";
+
+    /// Lays out selectable text with the id `[path]`, each test uses its own so layouts don't mix
+    fn layout(path: u32) -> Selectable<Text> {
+        let view = Text::new(TEXT).selectable();
+        let mut lctx_path = vec![path];
+        let mut lctx = LayoutContext {
+            font_manager: Rf::new(FontManager::new()),
+            path: &mut lctx_path,
+            scale_factor: 1.0,
+            environment: EnvironmentValues::new(),
+            needs_redraw: false,
+        };
+
+        view.layout(&mut lctx, Rect::new(0.0, 0.0, 1000.0, 1000.0));
+
+        view
+    }
+
+    /// Where the glyphs of `range` are drawn, in window space
+    fn point_in(path: u32, range: Range<usize>) -> Point {
+        let manager = get_id_manger();
+        let id = Id::from(vec![path]);
+        let origin = manager.get_layout(id.clone()).content_bounds.origin();
+        let rect = manager
+            .state::<TextLayout>(id)
+            .unwrap()
+            .selection_rects(range)[0];
+
+        rect.center() + origin.to_vec2()
+    }
+
+    fn send(view: &Selectable<Text>, path: u32, event: Event) -> bool {
+        let ectx = EventContext {
+            path: Rf::new(vec![path]),
+            scale_factor: 1.0,
+        };
+
+        view.event(ectx, &event)
+    }
+
+    fn click(view: &Selectable<Text>, path: u32, position: Point, click_count: u32) -> bool {
+        send(
+            view,
+            path,
+            Event::MouseDown {
+                position,
+                button: MouseButton::Left,
+                click_count,
+            },
+        )
+    }
+
+    fn selected_text(path: u32) -> &'static str {
+        let range = get_id_manger()
+            .state::<SelectionState>(Id::from(vec![path]))
+            .unwrap()
+            .range();
+
+        &TEXT[range]
+    }
+
+    #[test]
+    fn double_click_selects_word() {
+        let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let view = layout(1);
+
+        assert!(click(&view, 1, point_in(1, 6..11), 2));
+        assert_eq!(selected_text(1), "brave");
+    }
+
+    #[test]
+    fn triple_click_selects_line() {
+        let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let view = layout(2);
+
+        assert!(click(&view, 2, point_in(2, 18..24), 3));
+        assert_eq!(selected_text(2).trim_end(), "Second line");
+    }
+
+    #[test]
+    fn command_c_copies_selection() {
+        let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        set_clipboard(MemoryClipboard::new());
+        let view = layout(3);
+
+        assert!(click(&view, 3, point_in(3, 12..17), 2));
+
+        // Command on macOS and control elsewhere, holding both works on either
+        let copy = Event::KeyDown {
+            key: Key::Character('c'),
+            modifiers: Modifiers {
+                control: true,
+                logo: true,
+                ..Modifiers::default()
+            },
+        };
+        assert!(send(&view, 3, copy));
+        assert_eq!(get_clipboard_text().as_deref(), Some("world"));
+    }
+
+    #[test]
+    fn copy_without_selection_does_nothing() {
+        let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        set_clipboard(MemoryClipboard::new());
+        let view = layout(4);
+
+        // A single click only places the caret, which leaves nothing to copy
+        assert!(click(&view, 4, point_in(4, 0..5), 1));
+
+        let copy = Event::KeyDown {
+            key: Key::Character('c'),
+            modifiers: Modifiers {
+                control: true,
+                logo: true,
+                ..Modifiers::default()
+            },
+        };
+        assert!(!send(&view, 4, copy));
+        assert_eq!(get_clipboard_text(), None);
+    }
+}
@@ -0,0 +1,355 @@
+use vello::{
+    kurbo::{self, Affine, BezPath, Point, Rect, RoundedRect, Shape as _, Size},
+    peniko::{Brush, Stroke},
+};
+
+use crate::{
+    drawing::{DrawingContext, LayoutContext},
+    environment::{FillBrush, ForegroundColor, ShapeStroke},
+    layout::{get_id_manger, get_id_manger_mut},
+    view::{Element, Rectangle, View},
+};
+
+/// Tolerance used when flattening curved shapes into paths
+pub const SHAPE_TOLERANCE: f64 = 0.1;
+
+/// A shape that can be fit into a view's layout bounds
+///
+/// The shape views in this module draw themselves with the current fill brush and stroke, and can also be
+/// used with `.clip_shape(..)`. Any `Fn(Rect) -> BezPath` is a shape, which makes one-off clip shapes easy to write inline.
+pub trait Shape {
+    fn path(&self, bounds: Rect, scale_factor: f64) -> BezPath;
+}
+
+impl<F: Fn(Rect) -> BezPath> Shape for F {
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        self(bounds)
+    }
+}
+
+/// The layout bounds themselves, this is what `.clip()` clips to
+pub struct BoundsShape;
+
+impl Shape for BoundsShape {
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        bounds.to_path(SHAPE_TOLERANCE)
+    }
+}
+
+impl Shape for Rectangle {
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        bounds.to_path(SHAPE_TOLERANCE)
+    }
+}
+
+/// How shapes outline themselves, set for a subtree with the `.stroke(..)` modifier
+#[derive(Clone)]
+pub struct StrokeStyle {
+    pub width: f64,
+    /// `None` strokes with the foreground brush of wherever the shape is drawn
+    pub brush: Option<Brush>,
+    pub dash: Vec<f64>,
+}
+
+impl StrokeStyle {
+    /// Resolves the style to physical pixels
+    pub fn to_stroke(&self, scale_factor: f64) -> Stroke {
+        let stroke = Stroke::new((self.width * scale_factor) as f32);
+
+        if self.dash.is_empty() {
+            stroke
+        } else {
+            stroke.with_dashes(
+                0.0,
+                self.dash.iter().map(|dash| (dash * scale_factor) as f32),
+            )
+        }
+    }
+}
+
+/// Fills `path` with the current fill brush and outlines it with the current stroke, if any
+pub fn draw_path(dctx: &DrawingContext, path: &BezPath, filled: bool) {
+    let fill = dctx.env::<FillBrush>();
+    let mut builder = dctx.builder.borrow_mut();
+
+    if filled {
+        builder.fill(
+            vello::peniko::Fill::NonZero,
+            dctx.transform,
+            &fill,
+            None,
+            path,
+        );
+    }
+
+    match &dctx.env::<ShapeStroke>() {
+        Some(stroke) => builder.stroke(
+            &stroke.to_stroke(dctx.scale_factor),
+            dctx.transform,
+            &stroke
+                .brush
+                .clone()
+                .unwrap_or_else(|| dctx.env::<ForegroundColor>()),
+            None,
+            path,
+        ),
+        // Unfilled shapes (lines) would be invisible otherwise, so they're drawn like text
+        None if !filled => builder.stroke(
+            &Stroke::new(dctx.scale_factor as f32),
+            dctx.transform,
+            &dctx.env::<ForegroundColor>(),
+            None,
+            path,
+        ),
+        None => (),
+    }
+}
+
+/// Maps `path` from its own bounding box onto `bounds`
+fn fit_path(path: &BezPath, bounds: Rect) -> BezPath {
+    let source = path.bounding_box();
+    let scale_x = if source.width() > 0.0 {
+        bounds.width() / source.width()
+    } else {
+        1.0
+    };
+    let scale_y = if source.height() > 0.0 {
+        bounds.height() / source.height()
+    } else {
+        1.0
+    };
+
+    let mut path = path.clone();
+    path.apply_affine(
+        Affine::translate(bounds.origin().to_vec2())
+            * Affine::scale_non_uniform(scale_x, scale_y)
+            * Affine::translate(-source.origin().to_vec2()),
+    );
+    path
+}
+
+/// A shape with an intrinsic size, so it can be used as a view on its own
+trait SizedShape: Shape {
+    /// Size in logical pixels
+    fn size(&self) -> Size;
+
+    fn filled(&self) -> bool {
+        true
+    }
+}
+
+macro_rules! shape_view {
+    ($name:ident) => {
+        impl Element for $name {}
+
+        impl View for $name {
+            fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+                let size = self.size();
+                let rect = Rect::from_origin_size(
+                    available_rect.origin(),
+                    (
+                        size.width * lctx.scale_factor,
+                        size.height * lctx.scale_factor,
+                    ),
+                );
+
+                get_id_manger_mut().set_layout_content_rect(lctx.id(), rect);
+
+                rect
+            }
+
+            fn draw(&self, dctx: DrawingContext) {
+                let bounds = get_id_manger().get_layout(dctx.id()).content_bounds;
+
+                if dctx.is_clipped(bounds) {
+                    return;
+                }
+
+                draw_path(&dctx, &self.path(bounds, dctx.scale_factor), self.filled());
+            }
+        }
+    };
+}
+
+pub struct RoundedRectangle {
+    pub width: f64,
+    pub height: f64,
+    pub radius: f64,
+}
+
+impl RoundedRectangle {
+    pub fn new(width: f64, height: f64, radius: f64) -> RoundedRectangle {
+        RoundedRectangle {
+            width,
+            height,
+            radius,
+        }
+    }
+}
+
+impl Shape for RoundedRectangle {
+    fn path(&self, bounds: Rect, scale_factor: f64) -> BezPath {
+        RoundedRect::from_rect(bounds, self.radius * scale_factor).to_path(SHAPE_TOLERANCE)
+    }
+}
+
+impl SizedShape for RoundedRectangle {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+shape_view!(RoundedRectangle);
+
+pub struct Circle {
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(radius: f64) -> Circle {
+        Circle { radius }
+    }
+}
+
+impl Shape for Circle {
+    /// The largest circle centered in `bounds`
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        kurbo::Circle::new(bounds.center(), bounds.width().min(bounds.height()) / 2.0)
+            .to_path(SHAPE_TOLERANCE)
+    }
+}
+
+impl SizedShape for Circle {
+    fn size(&self) -> Size {
+        Size::new(self.radius * 2.0, self.radius * 2.0)
+    }
+}
+
+shape_view!(Circle);
+
+pub struct Ellipse {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Ellipse {
+    pub fn new(width: f64, height: f64) -> Ellipse {
+        Ellipse { width, height }
+    }
+}
+
+impl Shape for Ellipse {
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        kurbo::Ellipse::from_rect(bounds).to_path(SHAPE_TOLERANCE)
+    }
+}
+
+impl SizedShape for Ellipse {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+shape_view!(Ellipse);
+
+/// A rectangle whose short sides are fully rounded
+pub struct Capsule {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Capsule {
+    pub fn new(width: f64, height: f64) -> Capsule {
+        Capsule { width, height }
+    }
+}
+
+impl Shape for Capsule {
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        RoundedRect::from_rect(bounds, bounds.width().min(bounds.height()) / 2.0)
+            .to_path(SHAPE_TOLERANCE)
+    }
+}
+
+impl SizedShape for Capsule {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+shape_view!(Capsule);
+
+/// A line between two points, in logical pixels
+///
+/// Unlike the other shapes it isn't stretched to fit its view. The view is sized to the line's bounding box and
+/// the line is moved so that box starts at the view's origin
+///
+/// Lines are never filled, they're stroked with the current stroke or a hairline of the fill brush
+pub struct Line {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Line {
+    pub fn new(from: impl Into<Point>, to: impl Into<Point>) -> Line {
+        Line {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    fn line(&self) -> kurbo::Line {
+        kurbo::Line::new(self.from, self.to)
+    }
+}
+
+impl Shape for Line {
+    fn path(&self, bounds: Rect, scale_factor: f64) -> BezPath {
+        let line = self.line();
+        let mut path = line.to_path(SHAPE_TOLERANCE);
+
+        // The view is sized to the line's bounding box, whose origin goes at the view's origin
+        path.apply_affine(
+            Affine::translate(bounds.origin().to_vec2())
+                * Affine::scale(scale_factor)
+                * Affine::translate(-line.bounding_box().origin().to_vec2()),
+        );
+
+        path
+    }
+}
+
+impl SizedShape for Line {
+    fn size(&self) -> Size {
+        self.line().bounding_box().size()
+    }
+
+    fn filled(&self) -> bool {
+        false
+    }
+}
+
+shape_view!(Line);
+
+/// An arbitrary path, in logical pixels, that is stretched to fit its view
+pub struct Path(pub BezPath);
+
+impl Path {
+    pub fn new(path: BezPath) -> Path {
+        Path(path)
+    }
+}
+
+impl Shape for Path {
+    fn path(&self, bounds: Rect, _scale_factor: f64) -> BezPath {
+        fit_path(&self.0, bounds)
+    }
+}
+
+impl SizedShape for Path {
+    fn size(&self) -> Size {
+        self.0.bounding_box().size()
+    }
+}
+
+shape_view!(Path);
@@ -1,33 +1,301 @@
+use std::cell::{OnceCell, RefCell};
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use vello::fello::raw::FontRef;
-use vello::fello::MetadataProvider;
+use unicode_bidi::{BidiInfo, Level};
+use unicode_linebreak::BreakOpportunity;
+use unicode_segmentation::UnicodeSegmentation;
+use vello::fello::raw::types::Tag;
+use vello::fello::raw::{FileRef, FontRef, TableProvider};
+use vello::fello::{GlyphId, MetadataProvider};
 use vello::glyph::{Glyph, GlyphContext};
-use vello::kurbo::{Affine, Rect};
-use vello::peniko::{Blob, BrushRef, Font, StyleRef};
-use vello::{peniko::Brush, SceneBuilder};
+use vello::kurbo::{Affine, BezPath, Point, Rect, Size, Vec2};
+use vello::peniko::{BlendMode, Blob, BrushRef, Color, Compose, Fill, Font, Mix, Stroke, StyleRef};
+use vello::{peniko::Brush, SceneBuilder, SceneFragment};
 
 // This is very much a hack to get things working.
 // On Windows, can set this to "c:\\Windows\\Fonts\\seguiemj.ttf" to get color emoji
-const OPEN_SANS_DATA: &[u8] =
-    include_bytes!("../../res/fonts/Open_Sans/static/OpenSans-Regular.ttf");
-// const OPEN_SANS_DATA: &[u8] = include_bytes!("../../res/fonts/Open_Sans/OpenSans-VariableFont.ttf");
+const OPEN_SANS_DATA: &[u8] = include_bytes!("../../res/fonts/Open_Sans/OpenSans.ttf");
+const OPEN_SANS_ITALIC_DATA: &[u8] =
+    include_bytes!("../../res/fonts/Open_Sans/OpenSans-Italic-VariableFont_wdth,wght.ttf");
+
+/// The family used when none is set, both Open Sans faces are variable on `wght` and `wdth`
+pub const DEFAULT_FONT_FAMILY: &str = "opensans";
+pub const DEFAULT_FONT_SIZE: f32 = 20.0;
+/// How many glyph fragments the cache keeps before evicting the least recently used ones
+pub const DEFAULT_GLYPH_CACHE_CAPACITY: usize = 4096;
+const ELLIPSIS: &str = "\u{2026}";
+
+/// Weight on the usual 1-1000 scale, passed to variable fonts as the `wght` axis and used to pick the closest
+/// face of families with static faces
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontWeight(pub f32);
+
+impl FontWeight {
+    pub const THIN: FontWeight = FontWeight(100.0);
+    pub const EXTRA_LIGHT: FontWeight = FontWeight(200.0);
+    pub const LIGHT: FontWeight = FontWeight(300.0);
+    pub const REGULAR: FontWeight = FontWeight(400.0);
+    pub const MEDIUM: FontWeight = FontWeight(500.0);
+    pub const SEMI_BOLD: FontWeight = FontWeight(600.0);
+    pub const BOLD: FontWeight = FontWeight(700.0);
+    pub const EXTRA_BOLD: FontWeight = FontWeight(800.0);
+    pub const BLACK: FontWeight = FontWeight(900.0);
+}
+
+/// How the lines of multiline text line up with each other
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlignment {
+    #[default]
+    Leading,
+    Center,
+    Trailing,
+    /// Stretches every line but the last of each paragraph to the full width, by widening its spaces
+    Justified,
+}
+
+/// Where text that doesn't fit its line limit is cut and replaced with an ellipsis
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TruncationMode {
+    Head,
+    Middle,
+    #[default]
+    Tail,
+}
+
+/// How an underline or strikethrough is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecorationStyle {
+    #[default]
+    Solid,
+    Dotted,
+    Wavy,
+}
+
+/// An underline or strikethrough, drawn with the text's brush unless it has a color of its own
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextDecoration {
+    pub style: DecorationStyle,
+    pub color: Option<Color>,
+}
+
+impl TextDecoration {
+    pub fn solid() -> TextDecoration {
+        TextDecoration::default()
+    }
+
+    pub fn dotted() -> TextDecoration {
+        TextDecoration {
+            style: DecorationStyle::Dotted,
+            color: None,
+        }
+    }
+
+    pub fn wavy() -> TextDecoration {
+        TextDecoration {
+            style: DecorationStyle::Wavy,
+            color: None,
+        }
+    }
+
+    pub fn color(self, color: Color) -> TextDecoration {
+        TextDecoration {
+            color: Some(color),
+            ..self
+        }
+    }
+}
+
+/// The font settings text is laid out and drawn with, inherited down the tree from the font modifiers
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEnvironment {
+    /// Family name as registered with the `FontManager`, `None` for `DEFAULT_FONT_FAMILY`
+    pub family: Option<String>,
+    /// Size in logical pixels
+    pub size: f32,
+    pub weight: FontWeight,
+    /// Width as a percentage of normal, passed to variable fonts as the `wdth` axis and used to pick the
+    /// closest face of families with static faces
+    pub width: f32,
+    pub italic: bool,
+
+    pub alignment: TextAlignment,
+    /// The most lines the text is laid out on, `None` for as many as it needs
+    pub line_limit: Option<usize>,
+    pub truncation: TruncationMode,
+    /// Extra space between lines in logical pixels
+    pub line_spacing: f32,
+
+    pub underline: Option<TextDecoration>,
+    pub strikethrough: Option<TextDecoration>,
+    /// Color filled behind the glyphs, line high
+    pub highlight: Option<Color>,
+}
+
+impl Default for TextEnvironment {
+    fn default() -> Self {
+        Self {
+            family: None,
+            size: DEFAULT_FONT_SIZE,
+            weight: FontWeight::REGULAR,
+            width: 100.0,
+            italic: false,
+
+            alignment: TextAlignment::Leading,
+            line_limit: None,
+            truncation: TruncationMode::Tail,
+            line_spacing: 0.0,
+
+            underline: None,
+            strikethrough: None,
+            highlight: None,
+        }
+    }
+}
+
+impl TextEnvironment {
+    pub fn variations(&self) -> [(&'static str, f32); 2] {
+        [("wght", self.weight.0), ("wdth", self.width)]
+    }
+}
+
+/// What a face is selected by, read from its `OS/2`, `head` and `fvar` tables
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FaceTraits {
+    weight: f32,
+    /// Width as a percentage of normal
+    width: f32,
+    italic: bool,
+    /// Whether the face has a `wght` axis, in which case it can be drawn at any weight
+    variable_weight: bool,
+    /// Whether the face has a `wdth` axis, in which case it can be drawn at any width
+    variable_width: bool,
+}
+
+impl FaceTraits {
+    fn read(font: &FontRef) -> FaceTraits {
+        let os2 = font.os2().ok();
+        let axes = font.axes();
+        let has_axis = |tag: &[u8; 4]| axes.iter().any(|axis| axis.tag() == Tag::new(tag));
+
+        FaceTraits {
+            weight: os2
+                .as_ref()
+                .map_or(400.0, |os2| os2.us_weight_class() as f32),
+            width: os2
+                .as_ref()
+                .map_or(100.0, |os2| width_percentage(os2.us_width_class())),
+            italic: is_italic(font),
+            variable_weight: has_axis(b"wght"),
+            variable_width: has_axis(b"wdth"),
+        }
+    }
+
+    /// Whether both faces are the same style of a family, so one replaces the other when registered
+    fn same_style(&self, other: &FaceTraits) -> bool {
+        self.weight == other.weight && self.width == other.width && self.italic == other.italic
+    }
+
+    /// How far the face is from the environment's style: italic first, then width, then weight
+    fn distance(&self, text_env: &TextEnvironment) -> (bool, f32, f32) {
+        let width = if self.variable_width {
+            0.0
+        } else {
+            (self.width - text_env.width).abs()
+        };
+        let weight = if self.variable_weight {
+            0.0
+        } else {
+            (self.weight - text_env.weight.0).abs()
+        };
+
+        (self.italic != text_env.italic, width, weight)
+    }
+}
+
+/// A face registered under a family name
+#[derive(Debug)]
+struct FontFace {
+    traits: FaceTraits,
+    /// The file and face index the font is read from, for faces found by `load_system_fonts`
+    path: Option<(PathBuf, u32)>,
+    font: OnceCell<Option<Font>>,
+}
+
+impl FontFace {
+    fn loaded(font: Font) -> Option<FontFace> {
+        let traits = FaceTraits::read(&to_font_ref(&font)?);
+
+        Some(FontFace {
+            traits,
+            path: None,
+            font: OnceCell::from(Some(font)),
+        })
+    }
+
+    /// The face's font, read from its file the first time it's used. `None` if the file can't be read anymore
+    fn font(&self) -> Option<&Font> {
+        self.font
+            .get_or_init(|| {
+                let (path, index) = self.path.as_ref()?;
+                let data = std::fs::read(path).ok()?;
+                let font = Font::new(Blob::new(Arc::new(data)), *index);
+
+                to_font_ref(&font).is_some().then_some(font)
+            })
+            .as_ref()
+    }
+
+    /// Which of `chars` the face has glyphs for. Only the `cmap` table is read for faces that aren't loaded, and
+    /// it isn't kept
+    fn covers(&self, chars: &[char]) -> Vec<bool> {
+        let none = || vec![false; chars.len()];
+
+        if let Some(font) = self.font.get() {
+            return font
+                .as_ref()
+                .and_then(to_font_ref)
+                .map_or_else(none, |font| has_chars(&font, chars));
+        }
+
+        let cmap = self
+            .path
+            .as_ref()
+            .and_then(|(path, index)| read_tables(path, &[b"cmap"], Some(*index)).ok()?.pop());
+
+        cmap.as_deref()
+            .and_then(|data| FontRef::new(data).ok())
+            .map_or_else(none, |font| has_chars(&font, chars))
+    }
+}
 
 pub struct FontManager {
     gcx: GlyphContext,
-    fonts: HashMap<String, Font>,
+    /// The faces of each family, by lower case family name
+    fonts: HashMap<String, Vec<FontFace>>,
+    /// Families tried first when the selected font is missing a character
+    fallbacks: Vec<String>,
+    /// The family each character missing from the selected font was found in, by character and whether the text
+    /// is italic. `None` when no family has it. Kept across layouts and cleared when the fonts change
+    fallback_cache: RefCell<HashMap<(char, bool), Option<String>>>,
+    glyph_cache: GlyphCache,
 }
 
 impl std::fmt::Debug for FontManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FontManager")
             .field("fonts", &self.fonts)
+            .field("fallbacks", &self.fallbacks)
+            .field("glyph_cache", &self.glyph_cache_stats())
             .finish()
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TextAlign {
     Left,
     Right,
@@ -42,14 +310,152 @@ impl FontManager {
     pub fn new() -> Self {
         let mut fonts = HashMap::new();
         fonts.insert(
-            "opensans".to_string(),
-            Font::new(Blob::new(Arc::new(OPEN_SANS_DATA)), 0),
+            DEFAULT_FONT_FAMILY.to_string(),
+            [OPEN_SANS_DATA, OPEN_SANS_ITALIC_DATA]
+                .into_iter()
+                .filter_map(|data| FontFace::loaded(Font::new(Blob::new(Arc::new(data)), 0)))
+                .collect(),
         );
 
         Self {
             gcx: GlyphContext::new(),
             fonts,
+            fallbacks: Vec::new(),
+            fallback_cache: RefCell::default(),
+            glyph_cache: GlyphCache::new(DEFAULT_GLYPH_CACHE_CAPACITY),
+        }
+    }
+
+    pub fn glyph_cache_stats(&self) -> GlyphCacheStats {
+        GlyphCacheStats {
+            hits: self.glyph_cache.hits,
+            misses: self.glyph_cache.misses,
+            entries: self.glyph_cache.entries.len(),
+        }
+    }
+
+    pub fn reset_glyph_cache_stats(&mut self) {
+        self.glyph_cache.hits = 0;
+        self.glyph_cache.misses = 0;
+    }
+
+    /// Sets how many glyph fragments are kept, evicting the least recently used ones if there are more
+    pub fn set_glyph_cache_capacity(&mut self, capacity: usize) {
+        self.glyph_cache.capacity = capacity;
+        self.glyph_cache.evict(0);
+    }
+
+    /// Registers every face in `data` (a single font or a collection) under the family `name`
+    ///
+    /// Faces are picked by their weight, width and italic style, so calling this once per file of a family with
+    /// static faces lets the font modifiers select between them. Names are case insensitive, and a face replaces
+    /// any face of the family with the same style. Returns the number of faces registered, or `None` if `data`
+    /// isn't a font
+    pub fn register_font(
+        &mut self,
+        name: impl AsRef<str>,
+        data: impl Into<Vec<u8>>,
+    ) -> Option<usize> {
+        let faces = Self::faces(data.into())?;
+
+        Some(self.insert_faces(name.as_ref(), faces, true))
+    }
+
+    /// Reads a `.ttf`, `.otf` or `.ttc` file and registers its faces under their family name, returned as
+    /// registered (lower case)
+    pub fn load_font_file(&mut self, path: impl AsRef<Path>) -> io::Result<String> {
+        let data = std::fs::read(path)?;
+        let name = font_family_name(&data).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a font file or missing family name",
+            )
+        })?;
+
+        self.register_font(&name, data);
+
+        Ok(name.to_lowercase())
+    }
+
+    /// Finds every font in the fontconfig directories, without replacing faces that are already registered
+    ///
+    /// Only the tables that name and describe each face are read here, the rest of a face is read the first time
+    /// text uses its family. Returns the number of faces registered. This only reads the directories, matching
+    /// and aliasing rules from the fontconfig files are ignored
+    #[cfg(target_os = "linux")]
+    pub fn load_system_fonts(&mut self) -> usize {
+        let mut files = Vec::new();
+        for dir in fontconfig_dirs() {
+            collect_font_files(&dir, &mut files);
+        }
+
+        let mut loaded = 0;
+        for path in files {
+            let Ok(faces) = read_tables(&path, &SCAN_TABLES, None) else {
+                continue;
+            };
+
+            for (index, tables) in faces.iter().enumerate() {
+                let Ok(font) = FontRef::new(tables) else {
+                    continue;
+                };
+                let Some(name) = font_family_name(tables) else {
+                    continue;
+                };
+
+                let face = FontFace {
+                    traits: FaceTraits::read(&font),
+                    path: Some((path.clone(), index as u32)),
+                    font: OnceCell::new(),
+                };
+
+                loaded += self.insert_faces(&name, vec![face], false);
+            }
         }
+
+        loaded
+    }
+
+    /// Adds `faces` to the family `name`, returning how many were added
+    ///
+    /// A face with the same style as one already in the family replaces it if `replace` is set, and is skipped
+    /// otherwise
+    fn insert_faces(&mut self, name: &str, faces: Vec<FontFace>, replace: bool) -> usize {
+        self.fallback_cache.get_mut().clear();
+
+        let family = self.fonts.entry(name.to_lowercase()).or_default();
+        let mut inserted = 0;
+
+        for face in faces {
+            match family
+                .iter()
+                .position(|known| known.traits.same_style(&face.traits))
+            {
+                Some(index) if replace => family[index] = face,
+                Some(_) => continue,
+                None => family.push(face),
+            }
+
+            inserted += 1;
+        }
+
+        inserted
+    }
+
+    /// Splits font data into its faces
+    fn faces(data: Vec<u8>) -> Option<Vec<FontFace>> {
+        let blob = Blob::new(Arc::new(data));
+
+        let count = match FileRef::new(blob.as_ref()).ok()? {
+            FileRef::Font(_) => 1,
+            FileRef::Collection(collection) => collection.len(),
+        };
+
+        let faces = (0..count)
+            .filter_map(|index| FontFace::loaded(Font::new(blob.clone(), index)))
+            .collect::<Vec<_>>();
+
+        (!faces.is_empty()).then_some(faces)
     }
 
     // pub fn layout(&mut self, font: Option<&FontRef>, size: f32, text: &str, bounds: &Rect) -> Rect {
@@ -288,7 +694,9 @@ impl FontManager {
         style: impl Into<StyleRef<'a>>,
         text: &str,
     ) {
-        let default_font = self.fonts.get("opensans").unwrap();
+        let default_font = resolve_font(&self.fonts, &TextEnvironment::default())
+            .font()
+            .unwrap();
         // let default_font = if variations.is_empty() {
         //     &self.roboto
         // } else {
@@ -337,171 +745,1797 @@ impl FontManager {
             );
     }
 
-    pub fn layout(
+    /// Sets the families searched, in order, for characters the selected font doesn't have
+    ///
+    /// Any other registered font is tried after these, so this only decides which font wins when several have
+    /// the character
+    pub fn set_fallback_fonts(&mut self, names: impl IntoIterator<Item = impl AsRef<str>>) {
+        self.fallbacks = names
+            .into_iter()
+            .map(|name| name.as_ref().to_lowercase())
+            .collect();
+        self.fallback_cache.get_mut().clear();
+    }
+
+    /// The fonts the characters of `text` are looked up in: the selected font, then the family found for each
+    /// character it doesn't have
+    ///
+    /// Only the closest face of each family is used, and upright text never falls back to italic faces
+    fn fallback_candidates(&self, text_env: &TextEnvironment, text: &str) -> Vec<&FontFace> {
+        let primary = resolve_font(&self.fonts, text_env);
+        let charmap = primary
+            .font()
+            .and_then(to_font_ref)
+            .map(|font| font.charmap());
+
+        let mut missing = text
+            .chars()
+            .filter(|ch| {
+                !ch.is_control()
+                    && !charmap
+                        .as_ref()
+                        .and_then(|charmap| charmap.map(*ch))
+                        .map_or(false, |gid| gid != GlyphId::NOTDEF)
+            })
+            .collect::<Vec<_>>();
+        missing.sort_unstable();
+        missing.dedup();
+
+        self.find_fallbacks(&missing, text_env.italic);
+
+        let cache = self.fallback_cache.borrow();
+        let mut candidates = vec![primary];
+        for ch in missing {
+            let Some(Some(family)) = cache.get(&(ch, text_env.italic)) else {
+                continue;
+            };
+            let Some(face) = self
+                .fonts
+                .get(family)
+                .and_then(|faces| fallback_face(faces, text_env))
+            else {
+                continue;
+            };
+
+            if face.font().is_some()
+                && !candidates
+                    .iter()
+                    .any(|candidate| std::ptr::eq(*candidate, face))
+            {
+                candidates.push(face);
+            }
+        }
+
+        candidates
+    }
+
+    /// Finds the family of each of `chars` that isn't in the fallback cache yet, checking each face once for all
+    /// of them
+    ///
+    /// The fallback list is searched first, then everything else registered by name so the choice is stable.
+    /// Faces that aren't loaded only have their charmap read, so a face is only loaded once it has a character
+    fn find_fallbacks(&self, chars: &[char], italic: bool) {
+        let mut cache = self.fallback_cache.borrow_mut();
+        let mut missing = chars
+            .iter()
+            .copied()
+            .filter(|ch| !cache.contains_key(&(*ch, italic)))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+
+        let text_env = TextEnvironment {
+            italic,
+            ..TextEnvironment::default()
+        };
+        let mut names = self
+            .fonts
+            .keys()
+            .filter(|name| !self.fallbacks.contains(name))
+            .collect::<Vec<_>>();
+        names.sort();
+
+        for name in self.fallbacks.iter().chain(names) {
+            let Some(face) = self
+                .fonts
+                .get(name)
+                .and_then(|faces| fallback_face(faces, &text_env))
+            else {
+                continue;
+            };
+
+            let mut covered = face.covers(&missing).into_iter();
+            missing.retain(|ch| {
+                let found = covered.next().unwrap_or(false);
+                if found {
+                    cache.insert((*ch, italic), Some(name.clone()));
+                }
+
+                !found
+            });
+
+            if missing.is_empty() {
+                break;
+            }
+        }
+
+        for ch in missing {
+            cache.insert((ch, italic), None);
+        }
+    }
+
+    /// Splits `text` into runs that can each be drawn from a single font, picking the first candidate that has
+    /// each character
+    fn itemize(candidates: &[&FontFace], text: &str) -> Vec<FontRun> {
+        // Fallback charmaps are only parsed once a character isn't found in the fonts before them, faces that
+        // can't be read don't have any characters
+        let mut charmaps = (0..candidates.len()).map(|_| None).collect::<Vec<_>>();
+        let mut has_char = |index: usize, ch: char| {
+            charmaps[index]
+                .get_or_insert_with(|| {
+                    candidates[index]
+                        .font()
+                        .and_then(to_font_ref)
+                        .map(|font| font.charmap())
+                })
+                .as_ref()
+                .and_then(|charmap| charmap.map(ch))
+                .map_or(false, |gid| gid != GlyphId::NOTDEF)
+        };
+
+        let mut resolved = HashMap::new();
+        let mut runs: Vec<FontRun> = Vec::new();
+
+        for (offset, ch) in text.char_indices() {
+            let previous = runs.last().map(|run| run.font);
+
+            // Whitespace and marks stay with the run they're in, so clusters aren't split between fonts
+            let font = match previous {
+                Some(previous) if ch.is_control() => previous,
+                Some(previous)
+                    if (ch.is_whitespace() || continues_cluster(ch)) && has_char(previous, ch) =>
+                {
+                    previous
+                }
+                _ if ch.is_control() => 0,
+                _ => *resolved.entry(ch).or_insert_with(|| {
+                    (0..candidates.len())
+                        .find(|index| has_char(*index, ch))
+                        // Nothing has it, draw the selected font's missing glyph box
+                        .unwrap_or(0)
+                }),
+            };
+
+            let range = offset..offset + ch.len_utf8();
+            match runs.last_mut() {
+                Some(run) if run.font == font => run.range.end = range.end,
+                _ => runs.push(FontRun {
+                    font,
+                    style: 0,
+                    range,
+                    rtl: false,
+                }),
+            }
+        }
+
+        runs
+    }
+
+    /// Shapes the text and breaks it into lines to fit `width`, then lays each line out in visual order
+    ///
+    /// Lines break at UAX #14 opportunities, words that don't fit on a line of their own are broken between
+    /// clusters. Each line is reordered with the UAX #9 bidi algorithm
+    pub fn layout_text(
         &self,
-        font: Option<&Font>,
-        size: f32,
+        text_env: &TextEnvironment,
         scale: f32,
-        // transform: Affine,
-        bounds: Rect,
+        width: f64,
         text: &str,
-    ) -> Rect {
-        let default_font = self.fonts.get("opensans").unwrap();
-        // let default_font = FontRef::new(ROBOTO_FONT).unwrap();
-        let font = font
-            .and_then(to_font_ref)
-            .unwrap_or(to_font_ref(default_font).unwrap());
+    ) -> TextLayout {
+        self.layout_spans(
+            text_env,
+            &[(0..text.len(), text_env.clone())],
+            scale,
+            width,
+            text,
+        )
+    }
 
-        let fello_size = vello::fello::Size::new(size * scale);
-        let charmap = font.charmap();
-        let metrics = font.metrics(fello_size, Default::default());
-        let line_height = metrics.ascent - metrics.descent + metrics.leading;
-        let glyph_metrics = font.glyph_metrics(fello_size, Default::default());
-
-        let words: Vec<_> = text
-            .split(' ')
-            .map(|f| {
-                f.chars().chain([' '].into_iter()).fold(0.0, |acc, b| {
-                    acc + glyph_metrics
-                        .advance_width(charmap.map(b as u32).unwrap())
-                        .unwrap() as f64
+    /// Lays out text whose ranges are in different fonts as one paragraph, see `layout_text`
+    ///
+    /// `styles` cover the text in order. The alignment, line limit, truncation and line spacing come from
+    /// `text_env`, and each line is as tall as the tallest font on it
+    pub fn layout_spans(
+        &self,
+        text_env: &TextEnvironment,
+        styles: &[(Range<usize>, TextEnvironment)],
+        scale: f32,
+        width: f64,
+        text: &str,
+    ) -> TextLayout {
+        let default_style = [(0..text.len(), text_env.clone())];
+        let styles = if styles.is_empty() {
+            &default_style[..]
+        } else {
+            styles
+        };
+        let font_size = |style: usize| (styles[style].1.size * scale) as f64;
+        let style_at = |index: usize| {
+            styles
+                .iter()
+                .position(|(range, _)| range.contains(&index))
+                .unwrap_or(styles.len() - 1)
+        };
+
+        let bidi = BidiInfo::new(text, None);
+
+        // Every font used by any of the styles, runs and glyphs index into this
+        let mut fonts = Vec::new();
+        let mut primaries = Vec::with_capacity(styles.len());
+        let mut runs = Vec::new();
+
+        for (style, (range, style_env)) in styles.iter().enumerate() {
+            let candidates = self.fallback_candidates(style_env, &text[range.clone()]);
+            primaries.push(intern_font(&mut fonts, candidates[0].font().unwrap()));
+
+            // Runs are either in the selected font or in a candidate that had the character, both are loaded
+            for run in Self::itemize(&candidates, &text[range.clone()]) {
+                runs.push(FontRun {
+                    font: intern_font(&mut fonts, candidates[run.font].font().unwrap()),
+                    style,
+                    range: run.range.start + range.start..run.range.end + range.start,
+                    rtl: false,
+                });
+            }
+        }
+        let runs = split_levels(runs, &bidi.levels);
+
+        let (metrics, decoration_metrics): (Vec<_>, Vec<_>) = styles
+            .iter()
+            .zip(&primaries)
+            .map(|((_, style_env), font)| {
+                let font_ref = to_font_ref(fonts[*font]).unwrap();
+                let coords = font_ref
+                    .axes()
+                    .normalize(style_env.variations())
+                    .collect::<Vec<_>>();
+                let size = style_env.size * scale;
+                let metrics =
+                    font_ref.metrics(vello::fello::Size::new(size), coords.as_slice().into());
+
+                // Fonts without the metrics get lines a fourteenth of the size thick, the strikeout through
+                // the middle of the lower case letters
+                let fallback_thickness = size / 14.0;
+                let x_height = metrics.x_height.unwrap_or(metrics.ascent / 2.0);
+                let underline = metrics
+                    .underline
+                    .map(|underline| (underline.offset, underline.thickness))
+                    .unwrap_or((-size / 10.0, fallback_thickness));
+                let strikeout = metrics
+                    .strikeout
+                    .map(|strikeout| (strikeout.offset, strikeout.thickness))
+                    .unwrap_or((x_height / 2.0, fallback_thickness));
+
+                let line_metrics = LineMetrics {
+                    ascent: metrics.ascent as f64,
+                    descent: -metrics.descent as f64,
+                    leading: metrics.leading as f64,
+                };
+                let decoration_metrics = DecorationMetrics {
+                    underline_offset: underline.0 as f64,
+                    underline_thickness: underline.1.max(1.0) as f64,
+                    strikeout_offset: strikeout.0 as f64,
+                    strikeout_thickness: strikeout.1.max(1.0) as f64,
+                };
+
+                (line_metrics, decoration_metrics)
+            })
+            .unzip();
+
+        let shaped = runs
+            .iter()
+            .flat_map(|run| {
+                shape_run(
+                    fonts[run.font],
+                    &styles[run.style].1,
+                    font_size(run.style),
+                    &text[run.range.clone()],
+                    run.rtl,
+                )
+                .into_iter()
+                .map(|glyph| ShapedGlyph {
+                    font: run.font,
+                    style: run.style,
+                    cluster: glyph.cluster + run.range.start,
+                    ..glyph
                 })
             })
-            .chain([0.0].into_iter())
-            .collect();
+            .collect::<Vec<_>>();
 
-        let mut pen_x = 0f64;
-        let mut pen_y = 0f64;
-        let mut max_x = 0f64;
-        let mut word_index = 0;
+        // Advances summed up by byte offset, so the width of any range of the text is a subtraction
+        let mut prefix = vec![0.0; text.len() + 1];
+        let mut is_cluster = vec![false; text.len() + 1];
+        for glyph in &shaped {
+            prefix[glyph.cluster + 1] += glyph.advance;
+            is_cluster[glyph.cluster] = true;
+        }
+        for index in 1..prefix.len() {
+            prefix[index] += prefix[index - 1];
+        }
+        let measure = |range: Range<usize>| prefix[range.end] - prefix[range.start];
+        let trim_end = |range: Range<usize>| {
+            let start = range.start;
+            start..start + text[range].trim_end().len()
+        };
+
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        let mut last_fit = None;
         let mut overflow = false;
 
-        // let vars: [(&str, f32); 0] = [];
-        // let mut provider = self.gcx.new_provider(&font, None, size, false, vars);
+        for (position, opportunity) in unicode_linebreak::linebreaks(text) {
+            // Wrap at the last opportunity that fit, or inside the word if there wasn't one
+            while measure(trim_end(line_start..position)) > width {
+                let end = match last_fit.take() {
+                    Some(fit) => fit,
+                    None => {
+                        let content = trim_end(line_start..position);
+                        let fitting = (line_start + 1..content.end).rev().find(|index| {
+                            is_cluster[*index] && measure(line_start..*index) <= width
+                        });
+
+                        // At least one cluster goes on each line
+                        fitting.unwrap_or_else(|| {
+                            (line_start + 1..content.end)
+                                .find(|index| is_cluster[*index])
+                                .unwrap_or(content.end)
+                        })
+                    }
+                };
+
+                // Nothing left to split off (a single cluster, or a zero width line)
+                if end <= line_start || end >= position {
+                    break;
+                }
 
-        for ch in text.chars() {
-            if ch == '\n' {
-                pen_y += line_height as f64;
-                pen_x = 0.0;
-                continue;
+                lines.push((line_start..end, false));
+                line_start = end;
+                overflow = true;
             }
 
-            let gid = charmap.map(ch).unwrap_or_default();
-            let advance = glyph_metrics.advance_width(gid).unwrap_or_default() as f64;
+            match opportunity {
+                BreakOpportunity::Mandatory => {
+                    lines.push((line_start..position, true));
+                    line_start = position;
+                    last_fit = None;
+                }
+                BreakOpportunity::Allowed => last_fit = Some(position),
+            }
+        }
 
-            if pen_x + words[word_index + 1] > bounds.width() && ch == ' ' {
-                // if pen_x + advance > bounds.width() {
-                pen_x = 0.0;
-                pen_y += line_height as f64;
-                overflow = true;
+        if lines.is_empty() {
+            lines.push((0..0, true));
+        }
+
+        // Where a line of text cut short at either end can start or stop, within the paragraph it's in
+        let is_boundary =
+            |index: usize, range: &Range<usize>| index == range.end || is_cluster[index];
+        let fit_end = |range: Range<usize>, available: f64| {
+            (range.start..=range.end)
+                .rev()
+                .find(|index| {
+                    is_boundary(*index, &range) && measure(range.start..*index) <= available
+                })
+                .unwrap_or(range.start)
+        };
+        let fit_start = |range: Range<usize>, available: f64| {
+            (range.start..=range.end)
+                .find(|index| {
+                    is_boundary(*index, &range) && measure(*index..range.end) <= available
+                })
+                .unwrap_or(range.end)
+        };
+        let paragraph_end = |start: usize| {
+            let end = text[start..]
+                .find('\n')
+                .map_or(text.len(), |end| start + end);
+            trim_end(start..end)
+        };
+        let paragraph_start = |end: usize| text[..end].rfind('\n').map_or(0, |start| start + 1);
+
+        // A line broken at a soft hyphen shows a hyphen
+        let line_pieces = |(line, _): &(Range<usize>, bool), last: bool| {
+            let mut pieces = vec![LinePiece::Text(trim_end(line.clone()))];
+
+            if !last && text[line.clone()].ends_with('\u{AD}') {
+                let shy = line.end - '\u{AD}'.len_utf8();
+                let run = runs.iter().find(|run| run.range.contains(&shy)).unwrap();
+
+                pieces.push(LinePiece::Inserted {
+                    text: "-",
+                    font: run.font,
+                    style: run.style,
+                    at: line.end,
+                });
+            }
+
+            pieces
+        };
+        // An ellipsis standing in for the text hidden at `index`, in the style of the text there, and its width
+        let ellipsis = |index: usize, at: usize| {
+            let style = style_at(index);
+            let width = shape_run(
+                fonts[primaries[style]],
+                &styles[style].1,
+                font_size(style),
+                ELLIPSIS,
+                false,
+            )
+            .iter()
+            .map(|glyph| glyph.advance)
+            .sum::<f64>();
+
+            let piece = LinePiece::Inserted {
+                text: ELLIPSIS,
+                font: primaries[style],
+                style,
+                at,
+            };
+
+            (piece, width)
+        };
+
+        // The pieces each visible line is made of, and whether it's stretched when justified
+        let mut visible = Vec::with_capacity(lines.len());
+        let count = lines.len();
+
+        match text_env.line_limit.map(|limit| limit.max(1)) {
+            Some(limit) if count > limit => match text_env.truncation {
+                TruncationMode::Head => {
+                    let end = trim_end(lines[count - limit].0.clone()).end;
+                    let paragraph = paragraph_start(end);
+                    let (ellipsis, ellipsis_width) = ellipsis(end.saturating_sub(1), paragraph);
+                    let start = fit_start(paragraph..end, width - ellipsis_width);
+                    visible.push((vec![ellipsis, LinePiece::Text(start..end)], false));
+
+                    for (index, line) in lines.iter().enumerate().skip(count - limit + 1) {
+                        visible.push((line_pieces(line, index + 1 == count), !line.1));
+                    }
+                }
+                TruncationMode::Middle => {
+                    for (index, line) in lines.iter().enumerate().take(limit - 1) {
+                        visible.push((line_pieces(line, index + 1 == count), !line.1));
+                    }
+
+                    let left = paragraph_end(lines[limit - 1].0.start);
+                    let (ellipsis, ellipsis_width) = ellipsis(left.start, left.end);
+                    let half = (width - ellipsis_width) / 2.0;
+                    let left = left.start..fit_end(left.clone(), half);
+                    let right = trim_end(paragraph_start(text.len())..text.len());
+                    let right = fit_start(right.clone(), half).max(left.end)..right.end;
+
+                    visible.push((
+                        vec![LinePiece::Text(left), ellipsis, LinePiece::Text(right)],
+                        false,
+                    ));
+                }
+                TruncationMode::Tail => {
+                    for (index, line) in lines.iter().enumerate().take(limit - 1) {
+                        visible.push((line_pieces(line, index + 1 == count), !line.1));
+                    }
+
+                    let rest = paragraph_end(lines[limit - 1].0.start);
+                    let (ellipsis, ellipsis_width) = ellipsis(rest.start, rest.end);
+                    let end = fit_end(rest.clone(), width - ellipsis_width);
+                    visible.push((
+                        vec![LinePiece::Text(trim_end(rest.start..end)), ellipsis],
+                        false,
+                    ));
+                }
+            },
+            _ => {
+                for (index, line) in lines.iter().enumerate() {
+                    visible.push((line_pieces(line, index + 1 == count), !line.1));
+                }
             }
+        }
+
+        // Glyph indices in logical order, so a range of the text's glyphs is a contiguous slice
+        let mut logical = (0..shaped.len()).collect::<Vec<_>>();
+        logical.sort_by_key(|index| shaped[*index].cluster);
 
-            if ch == ' ' {
-                word_index += 1;
+        let spacing = text_env.line_spacing as f64 * scale as f64;
+        let mut glyphs = Vec::with_capacity(shaped.len());
+        let mut text_lines = Vec::with_capacity(visible.len());
+        let mut max_x = 0f64;
+        let mut top = 0f64;
+
+        for (pieces, justify) in &visible {
+            let range = pieces
+                .iter()
+                .filter_map(|piece| match piece {
+                    LinePiece::Text(range) => Some(range.clone()),
+                    LinePiece::Inserted { .. } => None,
+                })
+                .reduce(|first, last| first.start..last.end)
+                .unwrap_or(0..0);
+
+            // The line fits the tallest font on it, so differently sized spans share a baseline
+            let line_metrics = pieces
+                .iter()
+                .flat_map(|piece| match piece {
+                    LinePiece::Text(range) => styles
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, (style_range, _))| {
+                            style_range.start < range.end && range.start < style_range.end
+                        })
+                        .map(|(style, _)| metrics[style])
+                        .collect::<Vec<_>>(),
+                    LinePiece::Inserted { style, .. } => vec![metrics[*style]],
+                })
+                .reduce(LineMetrics::max)
+                .unwrap_or(metrics[style_at(range.start)]);
+
+            let baseline = top + line_metrics.ascent;
+            let first_glyph = glyphs.len();
+            let mut spaces = Vec::new();
+            let mut rtl = None;
+            let mut pen_x = 0f64;
+
+            for piece in pieces {
+                match piece {
+                    LinePiece::Text(content) if !content.is_empty() => {
+                        let Some(paragraph) = bidi
+                            .paragraphs
+                            .iter()
+                            .find(|paragraph| paragraph.range.contains(&content.start))
+                        else {
+                            continue;
+                        };
+                        rtl.get_or_insert(paragraph.level.is_rtl());
+
+                        let (levels, visual_runs) = bidi.visual_runs(paragraph, content.clone());
+
+                        for run in visual_runs {
+                            let run_rtl = levels[run.start].is_rtl();
+                            let from =
+                                logical.partition_point(|index| shaped[*index].cluster < run.start);
+                            let to =
+                                logical.partition_point(|index| shaped[*index].cluster < run.end);
+
+                            let mut run_glyphs = logical[from..to].to_vec();
+                            if run_rtl {
+                                // Stable, so glyphs within a cluster stay in the order shaping put them
+                                run_glyphs.sort_by_key(|index| Reverse(shaped[*index].cluster));
+                            }
+
+                            for index in run_glyphs {
+                                let glyph = &shaped[index];
+
+                                if text[glyph.cluster..].starts_with(' ') {
+                                    spaces.push(glyphs.len());
+                                }
+
+                                glyphs.push(PositionedGlyph {
+                                    font: glyph.font,
+                                    style: glyph.style,
+                                    id: glyph.id,
+                                    x: pen_x + glyph.offset.x,
+                                    y: baseline - glyph.offset.y,
+                                    cluster: glyph.cluster,
+                                    advance: glyph.advance,
+                                    rtl: run_rtl,
+                                });
+                                pen_x += glyph.advance;
+                            }
+                        }
+                    }
+                    LinePiece::Text(_) => (),
+                    LinePiece::Inserted {
+                        text,
+                        font,
+                        style,
+                        at,
+                    } => {
+                        let shaped = shape_run(
+                            fonts[*font],
+                            &styles[*style].1,
+                            font_size(*style),
+                            text,
+                            false,
+                        );
+
+                        for glyph in shaped {
+                            glyphs.push(PositionedGlyph {
+                                font: *font,
+                                style: *style,
+                                id: glyph.id,
+                                x: pen_x + glyph.offset.x,
+                                y: baseline - glyph.offset.y,
+                                cluster: *at,
+                                advance: glyph.advance,
+                                rtl: false,
+                            });
+                            pen_x += glyph.advance;
+                        }
+                    }
+                }
             }
 
-            // If newline starts with space, don't add it
-            if ch == ' ' && pen_y > 0.0 && pen_x < 0.1 {
-                continue;
+            // Justified lines share the space left over between their spaces
+            let justified = text_env.alignment == TextAlignment::Justified
+                && *justify
+                && width.is_finite()
+                && pen_x < width
+                && !spaces.is_empty();
+
+            if justified {
+                let extra = (width - pen_x) / spaces.len() as f64;
+                let mut spaces = spaces.into_iter().peekable();
+                let mut shift = 0.0;
+
+                for index in first_glyph..glyphs.len() {
+                    glyphs[index].x += shift;
+
+                    if spaces.next_if_eq(&index).is_some() {
+                        glyphs[index].advance += extra;
+                        shift += extra;
+                    }
+                }
+
+                pen_x = width;
             }
 
-            pen_x += advance;
+            let height = line_metrics.ascent + line_metrics.descent + line_metrics.leading;
+
+            text_lines.push(TextLine {
+                range,
+                glyphs: first_glyph..glyphs.len(),
+                top,
+                baseline,
+                height,
+                x: 0.0,
+                width: pen_x,
+                rtl: rtl.unwrap_or(false),
+            });
+            max_x = max_x.max(pen_x);
+            top += height + spacing;
+        }
+
+        if max_x > width || overflow {
+            max_x = width;
+        }
+
+        // Align the lines within the width of the text, leading is the right edge in right to left paragraphs
+        for line in text_lines.iter_mut() {
+            let align = match (text_env.alignment, line.rtl) {
+                (TextAlignment::Center, _) => TextAlign::Center,
+                (TextAlignment::Leading | TextAlignment::Justified, false)
+                | (TextAlignment::Trailing, true) => TextAlign::Left,
+                (TextAlignment::Leading | TextAlignment::Justified, true)
+                | (TextAlignment::Trailing, false) => TextAlign::Right,
+            };
+            let anchor = match align {
+                TextAlign::Center => max_x / 2.0,
+                TextAlign::Right => max_x,
+                _ => 0.0,
+            };
+            let ascent = line.baseline - line.top;
+            let (x, _) = xy_from_align(
+                ascent,
+                line.height - ascent,
+                Rect::new(0.0, 0.0, line.width, line.height),
+                TextAlign::Top,
+                align,
+            );
+
+            line.x = anchor + x;
+            for glyph in &mut glyphs[line.glyphs.clone()] {
+                glyph.x += line.x;
+            }
+        }
+
+        // Decorations cover each stretch of a line drawn in one style, in the order they're drawn
+        let mut decorations = Vec::new();
+
+        for line in &text_lines {
+            let mut stretches: Vec<(usize, f64, f64)> = Vec::new();
+
+            for glyph in &glyphs[line.glyphs.clone()] {
+                match stretches.last_mut() {
+                    Some((style, _, x1)) if *style == glyph.style => *x1 = glyph.x + glyph.advance,
+                    _ => stretches.push((glyph.style, glyph.x, glyph.x + glyph.advance)),
+                }
+            }
 
-            if pen_x > max_x {
-                max_x = pen_x
+            for (style, x0, x1) in stretches {
+                let style_env = &styles[style].1;
+                let decoration_metrics = &decoration_metrics[style];
+                let band = |offset: f64, thickness: f64| {
+                    let y = line.baseline - offset;
+                    Rect::new(x0, y - thickness / 2.0, x1, y + thickness / 2.0)
+                };
+
+                if let Some(color) = style_env.highlight {
+                    decorations.push(DecorationRun {
+                        kind: DecorationKind::Highlight(color),
+                        style,
+                        rect: Rect::new(x0, line.top, x1, line.top + line.height),
+                    });
+                }
+
+                if let Some(underline) = style_env.underline {
+                    decorations.push(DecorationRun {
+                        kind: DecorationKind::Underline(underline),
+                        style,
+                        rect: band(
+                            decoration_metrics.underline_offset,
+                            decoration_metrics.underline_thickness,
+                        ),
+                    });
+                }
+
+                if let Some(strikethrough) = style_env.strikethrough {
+                    decorations.push(DecorationRun {
+                        kind: DecorationKind::Strikethrough(strikethrough),
+                        style,
+                        rect: band(
+                            decoration_metrics.strikeout_offset,
+                            decoration_metrics.strikeout_thickness,
+                        ),
+                    });
+                }
             }
         }
 
-        if max_x > bounds.width() || overflow {
-            max_x = bounds.width();
+        TextLayout {
+            text: text.to_string(),
+            text_env: text_env.clone(),
+            styles: styles.to_vec(),
+            width,
+            scale,
+            fonts: fonts.into_iter().cloned().collect(),
+            glyphs,
+            lines: text_lines,
+            decorations,
+            size: Size::new(max_x.ceil(), (top - spacing).ceil()),
+            ascent: metrics[0].ascent,
+            descent: metrics[0].descent,
+            line_height: metrics[0].ascent + metrics[0].descent + metrics[0].leading,
         }
+    }
 
-        Rect::from_origin_size(bounds.origin(), (max_x.ceil(), (pen_y + line_height as f64).ceil()))
+    pub fn layout(
+        &self,
+        text_env: &TextEnvironment,
+        scale: f32,
+        // transform: Affine,
+        bounds: Rect,
+        text: &str,
+    ) -> Rect {
+        let layout = self.layout_text(text_env, scale, bounds.width(), text);
+
+        Rect::from_origin_size(bounds.origin(), layout.size)
     }
 
     pub fn add(
         &mut self,
         builder: &mut SceneBuilder,
-        font: Option<&Font>,
-        size: f32,
+        text_env: &TextEnvironment,
         scale: f32,
         brush: Option<&Brush>,
         transform: Affine,
         text: &str,
         bounds: Rect,
     ) {
-        let default_font = self.fonts.get("opensans").unwrap();
-        // let default_font = FontRef::new(ROBOTO_FONT).unwrap();
-        let font = font
-            .and_then(to_font_ref)
-            .unwrap_or(to_font_ref(default_font).unwrap());
+        let layout = self.layout_text(text_env, scale, bounds.width(), text);
+        let brushes = brush.into_iter().collect::<Vec<_>>();
 
-        let fello_size = vello::fello::Size::new(size * scale);
-        let charmap = font.charmap();
-        let metrics = font.metrics(fello_size, Default::default());
-        let line_height = metrics.ascent - metrics.descent + metrics.leading;
-        let glyph_metrics = font.glyph_metrics(fello_size, Default::default());
-
-        let words: Vec<_> = text
-            .split(' ')
-            .map(|f| {
-                f.chars().chain([' '].into_iter()).fold(0.0, |acc, b| {
-                    acc + glyph_metrics
-                        .advance_width(charmap.map(b as u32).unwrap())
-                        .unwrap() as f64
-                })
-            })
-            .chain([0.0].into_iter())
-            .collect();
+        self.draw_layout(builder, &layout, &brushes, transform, bounds.origin());
+    }
 
-        let mut pen_x = 0f64;
-        let mut pen_y = 0f64;
-        let mut word_index = 0;
+    /// Draws a layout made by `layout_text` or `layout_spans`, with its top left at `origin`
+    ///
+    /// `brushes` are indexed by style, styles past the end use the last one. Highlights go under the glyphs and
+    /// underlines and strikethroughs over them
+    pub fn draw_layout(
+        &mut self,
+        builder: &mut SceneBuilder,
+        layout: &TextLayout,
+        brushes: &[&Brush],
+        transform: Affine,
+        origin: Point,
+    ) {
+        let white = Brush::Solid(Color::WHITE);
+        let brush = |style: usize| {
+            brushes
+                .get(style)
+                .or(brushes.last())
+                .copied()
+                .unwrap_or(&white)
+        };
+        let (highlights, lines): (Vec<_>, Vec<_>) = layout
+            .decorations
+            .iter()
+            .partition(|run| matches!(run.kind, DecorationKind::Highlight(_)));
+
+        for run in highlights {
+            draw_decoration(builder, transform, origin, run, brush(run.style));
+        }
 
-        let vars: [(&str, f32); 0] = [];
-        let mut provider = self
-            .gcx
-            .new_provider(&font, None, size * scale, false, vars);
+        self.draw_glyphs(builder, layout, brushes, transform, origin);
 
-        for ch in text.chars() {
-            if ch == '\n' {
-                pen_y += line_height as f64;
-                pen_x = 0.0;
-                continue;
+        for run in lines {
+            draw_decoration(builder, transform, origin, run, brush(run.style));
+        }
+    }
+
+    /// Glyphs come out of the glyph cache as white outlines and are tinted with their brush, so the same
+    /// fragments serve every brush
+    fn draw_glyphs(
+        &mut self,
+        builder: &mut SceneBuilder,
+        layout: &TextLayout,
+        brushes: &[&Brush],
+        transform: Affine,
+        origin: Point,
+    ) {
+        if layout.glyphs.is_empty() {
+            return;
+        }
+
+        let mut groups = layout
+            .glyphs
+            .iter()
+            .map(|glyph| (glyph.font, glyph.style))
+            .collect::<Vec<_>>();
+        groups.sort_unstable();
+        groups.dedup();
+
+        if brushes.is_empty() {
+            for (font, style) in groups {
+                self.append_glyphs(builder, layout, font, style, transform, origin);
+            }
+            return;
+        }
+
+        // Leave room for glyphs reaching outside their line (italics, tall accents)
+        let bounds = Rect::from_origin_size(origin, layout.size)
+            .inflate(layout.line_height, layout.line_height);
+        let brush_index = |style: usize| style.min(brushes.len() - 1);
+
+        let mut tints = groups
+            .iter()
+            .map(|(_, style)| brush_index(*style))
+            .collect::<Vec<_>>();
+        tints.sort_unstable();
+        tints.dedup();
+
+        // Draw the white glyphs of each brush into a layer, then keep only the part of the brush they cover
+        for tint in tints {
+            builder.push_layer(Mix::Normal, 1.0, transform, &bounds);
+
+            for (font, style) in groups.iter().copied() {
+                if brush_index(style) == tint {
+                    self.append_glyphs(builder, layout, font, style, transform, origin);
+                }
             }
 
-            let gid = charmap.map(ch).unwrap_or_default();
-            let advance = glyph_metrics.advance_width(gid).unwrap_or_default() as f64;
+            builder.push_layer(
+                BlendMode::new(Mix::Normal, Compose::SrcIn),
+                1.0,
+                transform,
+                &bounds,
+            );
+            builder.fill(Fill::NonZero, transform, brushes[tint], None, &bounds);
+            builder.pop_layer();
+            builder.pop_layer();
+        }
+    }
+
+    /// Appends the glyphs of `layout` in one font and style, making fragments for the ones not in the cache
+    fn append_glyphs(
+        &mut self,
+        builder: &mut SceneBuilder,
+        layout: &TextLayout,
+        font: usize,
+        style: usize,
+        transform: Affine,
+        origin: Point,
+    ) {
+        let Some(font_ref) = to_font_ref(&layout.fonts[font]) else {
+            return;
+        };
+        let style_env = &layout.styles[style].1;
+        let variations = style_env.variations();
+
+        // Glyph providers borrow the glyph context, so one is only made once a glyph misses the cache
+        let mut provider = None;
+
+        let glyphs = layout
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.font == font && glyph.style == style);
+
+        for glyph in glyphs {
+            let key = GlyphKey {
+                font: layout.fonts[font].data.id(),
+                index: layout.fonts[font].index,
+                size: style_env.size.to_bits(),
+                scale: layout.scale.to_bits(),
+                id: glyph.id,
+                variations: variations.map(|(tag, value)| (tag, value.to_bits())),
+            };
+
+            let fragment = self.glyph_cache.get_or_insert_with(key, || {
+                provider
+                    .get_or_insert_with(|| {
+                        self.gcx.new_provider(
+                            &font_ref,
+                            None,
+                            style_env.size * layout.scale,
+                            false,
+                            variations,
+                        )
+                    })
+                    .get(glyph.id, None)
+            });
+
+            if let Some(fragment) = fragment {
+                let xform = transform
+                    * Affine::translate((origin.x + glyph.x, origin.y + glyph.y))
+                    * Affine::scale_non_uniform(1.0, -1.0);
 
-            if pen_x + words[word_index + 1] > bounds.width() && ch == ' ' {
-                pen_x = 0.0;
-                pen_y += line_height as f64;
+                builder.append(fragment, Some(xform));
             }
+        }
+    }
+}
+
+/// Characters that belong to the cluster before them (combining marks, joiners and variation selectors)
+fn continues_cluster(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{200C}'
+            | '\u{200D}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{E0100}'..='\u{E01EF}'
+    )
+}
 
-            if ch == ' ' {
-                word_index += 1;
+/// Splits font runs wherever the bidi embedding level changes, so each run is shaped in a single direction
+fn split_levels(runs: Vec<FontRun>, levels: &[Level]) -> Vec<FontRun> {
+    let mut split = Vec::with_capacity(runs.len());
+
+    for run in runs {
+        let mut start = run.range.start;
+
+        for index in run.range.clone().skip(1) {
+            if levels[index] != levels[start] {
+                split.push(FontRun {
+                    font: run.font,
+                    style: run.style,
+                    range: start..index,
+                    rtl: levels[start].is_rtl(),
+                });
+                start = index;
             }
-            // Skip space on start of newline
-            if ch == ' ' && pen_y > 0.0 && pen_x < 0.1 {
+        }
+
+        split.push(FontRun {
+            font: run.font,
+            style: run.style,
+            range: start..run.range.end,
+            rtl: levels[start].is_rtl(),
+        });
+    }
+
+    split
+}
+
+/// Shapes `text` with a single font into glyphs in visual order, for a size of `font_size` pixels per em
+fn shape_run(
+    font: &Font,
+    text_env: &TextEnvironment,
+    font_size: f64,
+    text: &str,
+    rtl: bool,
+) -> Vec<ShapedGlyph> {
+    let Some(mut face) = rustybuzz::Face::from_slice(font.data.as_ref(), font.index) else {
+        return Vec::new();
+    };
+
+    let variations = text_env
+        .variations()
+        .map(|(tag, value)| rustybuzz::Variation {
+            tag: rustybuzz::ttf_parser::Tag::from_bytes_lossy(tag.as_bytes()),
+            value,
+        });
+    face.set_variations(&variations);
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.set_direction(if rtl {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    buffer.guess_segment_properties();
+
+    let output = rustybuzz::shape(&face, &[], buffer);
+    let units = font_size / face.units_per_em() as f64;
+
+    output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            font: 0,
+            style: 0,
+            id: info.glyph_id as u16,
+            cluster: info.cluster as usize,
+            advance: position.x_advance as f64 * units,
+            offset: Vec2::new(
+                position.x_offset as f64 * units,
+                position.y_offset as f64 * units,
+            ),
+        })
+        .collect()
+}
+
+/// Part of a laid out line, either text from the string or glyphs that aren't in it (hyphens and ellipses)
+enum LinePiece {
+    Text(Range<usize>),
+    Inserted {
+        text: &'static str,
+        font: usize,
+        style: usize,
+        /// Byte offset in the text the inserted glyphs stand for, used when hit testing
+        at: usize,
+    },
+}
+
+/// A range of text drawn from a single font in a single style
+///
+/// `font` indexes the fallback candidates when itemizing, and the fonts of the whole layout after
+struct FontRun {
+    font: usize,
+    style: usize,
+    range: Range<usize>,
+    /// Set once the run is split by bidi level, for odd (right to left) levels
+    rtl: bool,
+}
+
+/// A glyph as it came out of shaping, before line breaking
+struct ShapedGlyph {
+    font: usize,
+    style: usize,
+    id: u16,
+    /// Byte offset of the first character the glyph was shaped from
+    cluster: usize,
+    advance: f64,
+    /// Offset from the pen position, y up
+    offset: Vec2,
+}
+
+#[derive(Clone, Copy)]
+struct LineMetrics {
+    ascent: f64,
+    /// Distance below the baseline, positive down
+    descent: f64,
+    leading: f64,
+}
+
+/// Where a style's underline and strikeout go, offsets are from the baseline, y up
+struct DecorationMetrics {
+    underline_offset: f64,
+    underline_thickness: f64,
+    strikeout_offset: f64,
+    strikeout_thickness: f64,
+}
+
+impl LineMetrics {
+    fn max(self, other: LineMetrics) -> LineMetrics {
+        LineMetrics {
+            ascent: self.ascent.max(other.ascent),
+            descent: self.descent.max(other.descent),
+            leading: self.leading.max(other.leading),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GlyphCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// A glyph outline at one size, the same whichever brush it's drawn with
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    /// Id of the font's data blob
+    font: u64,
+    /// Index of the face in the font's data
+    index: u32,
+    size: u32,
+    scale: u32,
+    id: u16,
+    variations: [(&'static str, u32); 2],
+}
+
+struct CachedGlyph {
+    /// `None` for glyphs without an outline, like spaces
+    fragment: Option<SceneFragment>,
+    last_used: u64,
+}
+
+/// Least recently used cache of glyph fragments, so text that doesn't change costs next to nothing to redraw
+struct GlyphCache {
+    entries: HashMap<GlyphKey, CachedGlyph>,
+    capacity: usize,
+    /// Bumped on every lookup, entries remember when they were last used by it
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl GlyphCache {
+    fn new(capacity: usize) -> GlyphCache {
+        GlyphCache {
+            entries: HashMap::new(),
+            capacity,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get_or_insert_with(
+        &mut self,
+        key: GlyphKey,
+        make: impl FnOnce() -> Option<SceneFragment>,
+    ) -> Option<&SceneFragment> {
+        self.clock += 1;
+
+        if self.entries.contains_key(&key) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            self.evict(1);
+        }
+
+        let clock = self.clock;
+        let entry = self.entries.entry(key).or_insert_with(|| CachedGlyph {
+            fragment: make(),
+            last_used: clock,
+        });
+        entry.last_used = clock;
+
+        entry.fragment.as_ref()
+    }
+
+    /// Makes room for `incoming` more entries
+    ///
+    /// Evicts down to three quarters of the capacity at once, so filling the cache doesn't sort it on every miss
+    fn evict(&mut self, incoming: usize) {
+        if self.entries.len() + incoming <= self.capacity {
+            return;
+        }
+
+        let keep = (self.capacity * 3 / 4).min(self.capacity.saturating_sub(incoming));
+        let mut ages = self
+            .entries
+            .values()
+            .map(|entry| entry.last_used)
+            .collect::<Vec<_>>();
+        ages.sort_unstable_by(|a, b| b.cmp(a));
+
+        if let Some(&newest_evicted) = ages.get(keep) {
+            self.entries
+                .retain(|_, entry| entry.last_used > newest_evicted);
+        }
+    }
+}
+
+/// A glyph positioned relative to the top left of the text, `y` being its baseline
+#[derive(Clone, Copy, Debug)]
+pub struct PositionedGlyph {
+    /// Index into `TextLayout::fonts`
+    pub font: usize,
+    /// Index into `TextLayout::styles`
+    pub style: usize,
+    pub id: u16,
+    pub x: f64,
+    pub y: f64,
+    /// Byte offset of the first character the glyph was shaped from
+    pub cluster: usize,
+    /// Horizontal space the glyph takes up on its line, including any justification
+    pub advance: f64,
+    /// Whether the glyph is in a right to left run
+    pub rtl: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecorationKind {
+    Highlight(Color),
+    Underline(TextDecoration),
+    Strikethrough(TextDecoration),
+}
+
+/// A decoration under, over or through a stretch of one line, relative to the top left of the text
+#[derive(Clone, Copy, Debug)]
+pub struct DecorationRun {
+    pub kind: DecorationKind,
+    /// Index into `TextLayout::styles`, the brush of which is used for decorations without a color
+    pub style: usize,
+    /// The line's height for highlights, and the thickness of the line centered on where it goes otherwise
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextLine {
+    /// Byte range of the text shown on the line, without trailing whitespace
+    pub range: Range<usize>,
+    /// Range of the line's glyphs in `TextLayout::glyphs`, in visual order
+    pub glyphs: Range<usize>,
+    pub top: f64,
+    pub baseline: f64,
+    pub height: f64,
+    /// Left edge of the line after alignment
+    pub x: f64,
+    pub width: f64,
+    /// Whether the line is in a right to left paragraph
+    pub rtl: bool,
+}
+
+/// Shaped, wrapped and positioned text, made once during layout and replayed when drawing
+///
+/// Views keep it in the `IdManager` and only lay the text out again when `is_valid_for` fails
+#[derive(Clone, Debug, Default)]
+pub struct TextLayout {
+    text: String,
+    text_env: TextEnvironment,
+    width: f64,
+    scale: f32,
+
+    /// The font of each range of the text, a single one covering it for plain text
+    pub styles: Vec<(Range<usize>, TextEnvironment)>,
+    pub fonts: Vec<Font>,
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<TextLine>,
+    pub decorations: Vec<DecorationRun>,
+    pub size: Size,
+
+    /// Metrics of the first style's font, in physical pixels
+    pub ascent: f64,
+    pub descent: f64,
+    pub line_height: f64,
+}
+
+impl TextLayout {
+    /// Whether the layout was made from the same string and font, for the same width and scale factor
+    pub fn is_valid_for(
+        &self,
+        text: &str,
+        text_env: &TextEnvironment,
+        width: f64,
+        scale: f32,
+    ) -> bool {
+        self.is_valid_for_spans(
+            text,
+            text_env,
+            &[(0..text.len(), text_env.clone())],
+            width,
+            scale,
+        )
+    }
+
+    /// Same as `is_valid_for`, for layouts made by `FontManager::layout_spans`
+    pub fn is_valid_for_spans(
+        &self,
+        text: &str,
+        text_env: &TextEnvironment,
+        styles: &[(Range<usize>, TextEnvironment)],
+        width: f64,
+        scale: f32,
+    ) -> bool {
+        self.text == text
+            && &self.text_env == text_env
+            && self.styles == styles
+            && self.width == width
+            && self.scale == scale
+    }
+
+    /// The string the layout was made from
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the caret position closest to `point`, relative to the top left of the text
+    pub fn index_at(&self, point: Point) -> usize {
+        let Some(line) = self
+            .lines
+            .iter()
+            .find(|line| point.y < line.top + line.height)
+            .or(self.lines.last())
+        else {
+            return 0;
+        };
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+
+        for glyph in glyphs {
+            if point.x < glyph.x + glyph.advance / 2.0 {
+                // The leading edge of a right to left glyph is its right side, so the left half is after it
+                return if glyph.rtl {
+                    self.cluster_end(line, glyph.cluster)
+                } else {
+                    glyph.cluster
+                };
+            }
+        }
+
+        if line.rtl {
+            line.range.start
+        } else {
+            line.range.end
+        }
+    }
+
+    /// Rectangles covering the glyphs of `range`, one per contiguous stretch of each line
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let mut rects = Vec::new();
+
+        if range.is_empty() {
+            return rects;
+        }
+
+        for line in &self.lines {
+            let mut current: Option<Rect> = None;
+
+            for glyph in &self.glyphs[line.glyphs.clone()] {
+                if !range.contains(&glyph.cluster) {
+                    continue;
+                }
+
+                let rect = Rect::new(
+                    glyph.x,
+                    line.top,
+                    glyph.x + glyph.advance,
+                    line.top + line.height,
+                );
+
+                current = match current {
+                    Some(last) if (rect.x0 - last.x1).abs() < 0.5 => Some(last.union(rect)),
+                    Some(last) => {
+                        rects.push(last);
+                        Some(rect)
+                    }
+                    None => Some(rect),
+                };
+            }
+
+            rects.extend(current);
+        }
+
+        rects
+    }
+
+    /// Byte range of the laid out line containing `index`
+    pub fn line_range(&self, index: usize) -> Range<usize> {
+        self.lines
+            .iter()
+            .find(|line| index <= line.range.end)
+            .or(self.lines.last())
+            .map(|line| line.range.clone())
+            .unwrap_or(0..0)
+    }
+
+    /// Byte range of the word (or run of whitespace or punctuation) containing `index`
+    pub fn word_range(&self, index: usize) -> Range<usize> {
+        self.text
+            .split_word_bound_indices()
+            .map(|(start, word)| start..start + word.len())
+            .find(|word| word.contains(&index))
+            .unwrap_or(index..index)
+    }
+
+    /// The end of the cluster starting at `cluster`, which is where the next one on the line starts
+    fn cluster_end(&self, line: &TextLine, cluster: usize) -> usize {
+        self.glyphs[line.glyphs.clone()]
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .filter(|start| *start > cluster)
+            .min()
+            .unwrap_or(line.range.end)
+    }
+}
+
+/// Draws a decoration of a layout with its top left at `origin`, `brush` being the one of the text it decorates
+fn draw_decoration(
+    builder: &mut SceneBuilder,
+    transform: Affine,
+    origin: Point,
+    run: &DecorationRun,
+    brush: &Brush,
+) {
+    let rect = run.rect + origin.to_vec2();
+    let decoration = match run.kind {
+        DecorationKind::Highlight(color) => {
+            builder.fill(Fill::NonZero, transform, &Brush::Solid(color), None, &rect);
+            return;
+        }
+        DecorationKind::Underline(decoration) | DecorationKind::Strikethrough(decoration) => {
+            decoration
+        }
+    };
+
+    let color = decoration.color.map(Brush::Solid);
+    let brush = color.as_ref().unwrap_or(brush);
+    let thickness = rect.height();
+    let y = rect.center().y;
+
+    match decoration.style {
+        DecorationStyle::Solid => builder.fill(Fill::NonZero, transform, brush, None, &rect),
+        DecorationStyle::Dotted => {
+            let mut line = BezPath::new();
+            line.move_to((rect.x0, y));
+            line.line_to((rect.x1, y));
+
+            builder.stroke(
+                &Stroke::new(thickness as f32)
+                    .with_dashes(0.0, [thickness as f32, thickness as f32]),
+                transform,
+                brush,
+                None,
+                &line,
+            );
+        }
+        DecorationStyle::Wavy => {
+            // Half waves twice the thickness long, swinging a thickness either side of the line
+            let half = thickness * 2.0;
+            let mut wave = BezPath::new();
+            let mut x = rect.x0;
+            let mut up = true;
+
+            wave.move_to((x, y));
+            while x < rect.x1 {
+                let next = (x + half).min(rect.x1);
+                let peak = if up { y - half } else { y + half };
+
+                wave.quad_to(((x + next) / 2.0, peak), (next, y));
+                x = next;
+                up = !up;
+            }
+
+            builder.stroke(
+                &Stroke::new(thickness as f32),
+                transform,
+                brush,
+                None,
+                &wave,
+            );
+        }
+    }
+}
+
+/// Index of `font` in `fonts`, adding it if it isn't there yet
+fn intern_font<'a>(fonts: &mut Vec<&'a Font>, font: &'a Font) -> usize {
+    match fonts.iter().position(|known| std::ptr::eq(*known, font)) {
+        Some(index) => index,
+        None => {
+            fonts.push(font);
+            fonts.len() - 1
+        }
+    }
+}
+
+/// Picks the face of the environment's family closest to its style, falling back to the default family
+///
+/// Faces are tried from the closest, only those up to the first one that can be read are loaded
+fn resolve_font<'a>(
+    fonts: &'a HashMap<String, Vec<FontFace>>,
+    text_env: &TextEnvironment,
+) -> &'a FontFace {
+    let family = text_env
+        .family
+        .as_deref()
+        .unwrap_or(DEFAULT_FONT_FAMILY)
+        .to_lowercase();
+
+    [family.as_str(), DEFAULT_FONT_FAMILY]
+        .into_iter()
+        .filter_map(|family| fonts.get(family))
+        .find_map(|faces| {
+            let mut faces = faces.iter().collect::<Vec<_>>();
+            faces.sort_by(|a, b| compare_distance(a, b, text_env));
+
+            faces.into_iter().find(|face| face.font().is_some())
+        })
+        .unwrap()
+}
+
+/// The face closest to the environment's style, the first registered one if several are as close
+fn best_face<'a>(
+    faces: impl IntoIterator<Item = &'a FontFace>,
+    text_env: &TextEnvironment,
+) -> Option<&'a FontFace> {
+    faces
+        .into_iter()
+        .min_by(|a, b| compare_distance(a, b, text_env))
+}
+
+/// Orders faces by how close they are to the environment's style
+fn compare_distance(a: &FontFace, b: &FontFace, text_env: &TextEnvironment) -> std::cmp::Ordering {
+    a.traits
+        .distance(text_env)
+        .partial_cmp(&b.traits.distance(text_env))
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// The face of a family to look up missing characters in, italic faces are only used for italic text
+fn fallback_face<'a>(faces: &'a [FontFace], text_env: &TextEnvironment) -> Option<&'a FontFace> {
+    best_face(
+        faces
+            .iter()
+            .filter(|face| text_env.italic || !face.traits.italic),
+        text_env,
+    )
+}
+
+/// Converts an `OS/2` width class to a percentage of normal width
+fn width_percentage(width_class: u16) -> f32 {
+    match width_class {
+        1 => 50.0,
+        2 => 62.5,
+        3 => 75.0,
+        4 => 87.5,
+        6 => 112.5,
+        7 => 125.0,
+        8 => 150.0,
+        9 => 200.0,
+        _ => 100.0,
+    }
+}
+
+fn is_italic(font: &FontRef) -> bool {
+    // Bit 1 of macStyle is set for italic faces
+    font.head()
+        .map(|head| head.mac_style() & 0b10 != 0)
+        .unwrap_or(false)
+}
+
+/// The typographic family name (name id 16) of the first face in `data`, or the legacy family name (id 1)
+fn font_family_name(data: &[u8]) -> Option<String> {
+    let font = match FileRef::new(data).ok()? {
+        FileRef::Font(font) => font,
+        FileRef::Collection(collection) => collection.get(0).ok()?,
+    };
+    let name = font.name().ok()?;
+
+    [16, 1].into_iter().find_map(|name_id| {
+        name.name_record()
+            .iter()
+            .filter(|record| record.name_id() == name_id)
+            .find_map(|record| record.string(name.string_data()).ok())
+            .map(|string| string.chars().collect::<String>())
+            .filter(|string| !string.is_empty())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn fontconfig_dirs() -> Vec<PathBuf> {
+    let config_path = Path::new("/etc/fonts/fonts.conf");
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+    ];
+    if let Some(data_home) = &data_home {
+        dirs.push(data_home.join("fonts"));
+    }
+    if let Some(home) = &home {
+        dirs.push(home.join(".fonts"));
+    }
+
+    // Pick up any extra `<dir>` entries, e.g. from distributions that keep fonts elsewhere
+    if let Ok(config) = std::fs::read_to_string(config_path) {
+        let config = strip_xml_comments(&config);
+
+        for entry in config.split("<dir").skip(1) {
+            let Some(start) = entry.find('>') else {
+                continue;
+            };
+            let Some(end) = entry.find("</dir>") else {
+                continue;
+            };
+            if start >= end {
                 continue;
             }
 
-            if let Some(glyph) = provider.get(gid.to_u16(), brush) {
-                let xform = transform
-                    * Affine::translate((bounds.x0, bounds.y0 + metrics.ascent as f64))
-                    * Affine::translate((pen_x, pen_y))
-                    * Affine::scale_non_uniform(1.0, -1.0);
-              
-                builder.append(&glyph, Some(xform));
+            let attributes = &entry[..start];
+            let dir = entry[start + 1..end].trim();
+
+            // Relative paths without a prefix are relative to the working directory, which says nothing about
+            // where fonts are, so they're dropped along with paths whose base directory isn't known
+            let dir = if attributes.contains(r#"prefix="xdg""#) {
+                data_home.as_ref().map(|data_home| data_home.join(dir))
+            } else if attributes.contains(r#"prefix="relative""#) {
+                config_path.parent().map(|parent| parent.join(dir))
+            } else if let Some(rest) = dir.strip_prefix("~/") {
+                home.as_ref().map(|home| home.join(rest))
+            } else {
+                Some(PathBuf::from(dir)).filter(|dir| dir.is_absolute())
+            };
+            let Some(dir) = dir else {
+                continue;
+            };
+
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
             }
+        }
+    }
+
+    dirs
+}
 
-            pen_x += advance;
+/// Removes `<!-- -->` comments, so commented out entries aren't read
+#[cfg(target_os = "linux")]
+fn strip_xml_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("<!--") {
+        stripped.push_str(&rest[..start]);
+
+        rest = match rest[start..].find("-->") {
+            Some(end) => &rest[start + end + 3..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+
+    stripped
+}
+
+/// Tables read when scanning for system fonts, enough to name a face and select it by style. Sorted, like the
+/// table records of a font
+#[cfg(target_os = "linux")]
+const SCAN_TABLES: [&[u8; 4]; 4] = [b"OS/2", b"fvar", b"head", b"name"];
+
+/// Reads the tables in `tags` of each face in a font file, or only of the face at `index`, without reading the
+/// rest of the file
+///
+/// Each face is returned as a font holding only those tables, which is enough for `FontRef` to read them. `tags`
+/// have to be sorted, like the table records of a font
+fn read_tables(path: &Path, tags: &[&[u8; 4]], index: Option<u32>) -> io::Result<Vec<Vec<u8>>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut read_at = |offset: u64, size: u64| -> io::Result<Vec<u8>> {
+        if offset + size > len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "table past the end of the file",
+            ));
+        }
+
+        let mut bytes = vec![0; size as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut bytes)?;
+
+        Ok(bytes)
+    };
+    let u32_at = |bytes: &[u8], offset: usize| {
+        u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as u64
+    };
+
+    let header = read_at(0, 12)?;
+    let face_offsets = if &header[..4] == b"ttcf" {
+        let count = u32_at(&header, 8);
+
+        read_at(12, count * 4)?
+            .chunks_exact(4)
+            .map(|offset| u32_at(offset, 0))
+            .collect()
+    } else {
+        vec![0]
+    };
+    let face_offsets = match index {
+        Some(index) => face_offsets
+            .get(index as usize)
+            .copied()
+            .into_iter()
+            .collect(),
+        None => face_offsets,
+    };
+
+    let mut faces = Vec::with_capacity(face_offsets.len());
+    for offset in face_offsets {
+        let header = read_at(offset, 12)?;
+        let table_count = u16::from_be_bytes([header[4], header[5]]) as u64;
+        let records = read_at(offset + 12, table_count * 16)?;
+
+        let mut tables = Vec::new();
+        for record in records.chunks_exact(16) {
+            if tags.iter().any(|tag| tag[..] == record[..4]) {
+                tables.push((
+                    &record[..4],
+                    read_at(u32_at(record, 8), u32_at(record, 12))?,
+                ));
+            }
+        }
+
+        // An sfnt header followed by the table records, the search fields are left at zero as they aren't used
+        let mut face = header[..4].to_vec();
+        face.extend((tables.len() as u16).to_be_bytes());
+        face.extend([0; 6]);
+
+        let mut table_offset = 12 + 16 * tables.len();
+        for (tag, table) in &tables {
+            face.extend_from_slice(tag);
+            face.extend([0; 4]);
+            face.extend((table_offset as u32).to_be_bytes());
+            face.extend((table.len() as u32).to_be_bytes());
+            table_offset += table.len();
+        }
+        for (_, table) in tables {
+            face.extend(table);
+        }
+
+        faces.push(face);
+    }
+
+    Ok(faces)
+}
+
+#[cfg(target_os = "linux")]
+fn collect_font_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_font_files(&path, files);
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| matches!(ext.to_lowercase().as_str(), "ttf" | "otf" | "ttc"))
+            .unwrap_or(false)
+        {
+            files.push(path);
         }
     }
 }
 
+/// Whether `font` has a glyph for each of `chars`
+fn has_chars(font: &FontRef, chars: &[char]) -> Vec<bool> {
+    let charmap = font.charmap();
+
+    chars
+        .iter()
+        .map(|ch| charmap.map(*ch).map_or(false, |gid| gid != GlyphId::NOTDEF))
+        .collect()
+}
+
 fn to_font_ref(font: &Font) -> Option<FontRef<'_>> {
-    use vello::fello::raw::FileRef;
     let file_ref = FileRef::new(font.data.as_ref()).ok()?;
     match file_ref {
         FileRef::Font(font) => Some(font),
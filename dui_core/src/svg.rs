@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use usvg::{NodeExt, TreeParsing};
+use vello::{
+    kurbo::{Affine, BezPath, Point, Rect, Shape, Size},
+    peniko::{BlendMode, Brush, Cap, Color, ColorStop, Compose, Gradient, Join, Mix, Stroke},
+    SceneBuilder, SceneFragment,
+};
+
+use crate::{
+    drawing::{DrawingContext, LayoutContext},
+    environment::ForegroundColor,
+    layout::{get_id_manger, get_id_manger_mut},
+    view::{Element, View},
+};
+
+lazy_static::lazy_static! {
+    static ref SVG_CACHE: RwLock<HashMap<SvgKey, Result<Arc<ParsedSvg>, Arc<io::Error>>>> = RwLock::new(HashMap::new());
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum SvgKey {
+    Path(PathBuf),
+    Static(usize, usize),
+}
+
+enum SvgSource {
+    Path(PathBuf),
+    Static(&'static [u8]),
+}
+
+/// An svg converted to a scene fragment in its own view box space
+struct ParsedSvg {
+    fragment: SceneFragment,
+    view_box: Rect,
+    size: Size,
+}
+
+impl ParsedSvg {
+    fn parse(source: &SvgSource) -> io::Result<ParsedSvg> {
+        let data = match source {
+            SvgSource::Path(path) => std::fs::read(path)?,
+            SvgSource::Static(bytes) => bytes.to_vec(),
+        };
+
+        let tree = usvg::Tree::from_data(&data, &usvg::Options::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+        let view_box = tree.view_box.rect;
+        let view_box = Rect::new(
+            view_box.x() as f64,
+            view_box.y() as f64,
+            view_box.right() as f64,
+            view_box.bottom() as f64,
+        );
+
+        let mut fragment = SceneFragment::default();
+        {
+            let mut builder = SceneBuilder::for_fragment(&mut fragment);
+
+            append_children(&mut builder, &tree.root, view_box);
+        }
+
+        Ok(ParsedSvg {
+            fragment,
+            view_box,
+            size: Size::new(tree.size.width() as f64, tree.size.height() as f64),
+        })
+    }
+}
+
+/// Appends the paths below `parent`, in the view box space since every path uses its absolute transform
+///
+/// Group opacity is applied with a layer clipped to the view box. Clip paths, masks, filters, blend modes and
+/// patterns are not supported and draw as if they weren't there.
+fn append_children(builder: &mut SceneBuilder, parent: &usvg::Node, view_box: Rect) {
+    for node in parent.children() {
+        match &*node.borrow() {
+            usvg::NodeKind::Group(group) => {
+                let opacity = group.opacity.get();
+
+                if opacity < 1.0 {
+                    builder.push_layer(BlendMode::default(), opacity, Affine::IDENTITY, &view_box);
+                    append_children(builder, &node, view_box);
+                    builder.pop_layer();
+                } else {
+                    append_children(builder, &node, view_box);
+                }
+            }
+            usvg::NodeKind::Path(path) => {
+                append_path(builder, path, to_affine(node.abs_transform()))
+            }
+            _ => append_children(builder, &node, view_box),
+        }
+    }
+}
+
+fn append_path(builder: &mut SceneBuilder, path: &usvg::Path, transform: Affine) {
+    let bez_path = to_bez_path(&path.data);
+    let bbox = bez_path.bounding_box();
+
+    if let Some(fill) = &path.fill {
+        if let Some((brush, brush_transform)) = to_brush(&fill.paint, fill.opacity, bbox) {
+            let rule = match fill.rule {
+                usvg::FillRule::NonZero => vello::peniko::Fill::NonZero,
+                usvg::FillRule::EvenOdd => vello::peniko::Fill::EvenOdd,
+            };
+
+            builder.fill(rule, transform, &brush, Some(brush_transform), &bez_path);
+        }
+    }
+
+    if let Some(stroke) = &path.stroke {
+        if let Some((brush, brush_transform)) = to_brush(&stroke.paint, stroke.opacity, bbox) {
+            builder.stroke(
+                &to_stroke(stroke),
+                transform,
+                &brush,
+                Some(brush_transform),
+                &bez_path,
+            );
+        }
+    }
+}
+
+fn to_stroke(stroke: &usvg::Stroke) -> Stroke {
+    let cap = match stroke.linecap {
+        usvg::LineCap::Butt => Cap::Butt,
+        usvg::LineCap::Round => Cap::Round,
+        usvg::LineCap::Square => Cap::Square,
+    };
+    let join = match stroke.linejoin {
+        usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => Join::Miter,
+        usvg::LineJoin::Round => Join::Round,
+        usvg::LineJoin::Bevel => Join::Bevel,
+    };
+
+    let result = Stroke::new(stroke.width.get())
+        .with_caps(cap)
+        .with_join(join)
+        .with_miter_limit(stroke.miterlimit.get());
+
+    match &stroke.dasharray {
+        Some(dashes) => result.with_dashes(stroke.dashoffset, dashes.iter().copied()),
+        None => result,
+    }
+}
+
+fn to_affine(transform: usvg::Transform) -> Affine {
+    let usvg::Transform {
+        sx,
+        kx,
+        ky,
+        sy,
+        tx,
+        ty,
+    } = transform;
+
+    Affine::new([sx, ky, kx, sy, tx, ty].map(f64::from))
+}
+
+fn to_bez_path(data: &usvg::tiny_skia_path::Path) -> BezPath {
+    use usvg::tiny_skia_path::PathSegment;
+
+    let mut path = BezPath::new();
+
+    for segment in data.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => path.move_to((p.x as f64, p.y as f64)),
+            PathSegment::LineTo(p) => path.line_to((p.x as f64, p.y as f64)),
+            PathSegment::QuadTo(p1, p) => {
+                path.quad_to((p1.x as f64, p1.y as f64), (p.x as f64, p.y as f64))
+            }
+            PathSegment::CubicTo(p1, p2, p) => path.curve_to(
+                (p1.x as f64, p1.y as f64),
+                (p2.x as f64, p2.y as f64),
+                (p.x as f64, p.y as f64),
+            ),
+            PathSegment::Close => path.close_path(),
+        }
+    }
+
+    path
+}
+
+fn to_color(color: usvg::Color, opacity: f32) -> Color {
+    Color::rgba8(
+        color.red,
+        color.green,
+        color.blue,
+        (opacity.clamp(0.0, 1.0) * 255.0).round() as u8,
+    )
+}
+
+fn to_stops(stops: &[usvg::Stop], opacity: f32) -> Vec<ColorStop> {
+    stops
+        .iter()
+        .map(|stop| ColorStop {
+            offset: stop.offset.get(),
+            color: to_color(stop.color, stop.opacity.get() * opacity),
+        })
+        .collect()
+}
+
+/// Converts an svg paint to a brush, along with the transform from the gradient's space
+///
+/// `bbox` is the bounds of the painted path, which `objectBoundingBox` gradients are relative to
+fn to_brush(paint: &usvg::Paint, opacity: usvg::Opacity, bbox: Rect) -> Option<(Brush, Affine)> {
+    let opacity = opacity.get();
+
+    match paint {
+        usvg::Paint::Color(color) => {
+            Some((Brush::Solid(to_color(*color, opacity)), Affine::IDENTITY))
+        }
+        usvg::Paint::LinearGradient(gradient) => {
+            let brush = Gradient::new_linear(
+                Point::new(gradient.x1 as f64, gradient.y1 as f64),
+                Point::new(gradient.x2 as f64, gradient.y2 as f64),
+            )
+            .with_stops(to_stops(&gradient.stops, opacity).as_slice());
+
+            Some((Brush::Gradient(brush), gradient_transform(gradient, bbox)))
+        }
+        usvg::Paint::RadialGradient(gradient) => {
+            let brush = Gradient::new_two_point_radial(
+                Point::new(gradient.fx as f64, gradient.fy as f64),
+                0.0,
+                Point::new(gradient.cx as f64, gradient.cy as f64),
+                gradient.r.get(),
+            )
+            .with_stops(to_stops(&gradient.stops, opacity).as_slice());
+
+            Some((Brush::Gradient(brush), gradient_transform(gradient, bbox)))
+        }
+        usvg::Paint::Pattern(_) => None,
+    }
+}
+
+fn gradient_transform(gradient: &usvg::BaseGradient, bbox: Rect) -> Affine {
+    let transform = to_affine(gradient.transform);
+
+    match gradient.units {
+        usvg::Units::UserSpaceOnUse => transform,
+        usvg::Units::ObjectBoundingBox => {
+            Affine::translate(bbox.origin().to_vec2())
+                * Affine::scale_non_uniform(bbox.width(), bbox.height())
+                * transform
+        }
+    }
+}
+
+/// An svg icon, parsed into a scene fragment once and scaled to the view's frame
+pub struct Svg {
+    source: SvgSource,
+    key: SvgKey,
+    size: Option<Size>,
+    tint: bool,
+}
+
+impl Svg {
+    pub fn from_path(path: impl Into<PathBuf>) -> Svg {
+        let path = path.into();
+
+        Svg {
+            source: SvgSource::Path(path.clone()),
+            key: SvgKey::Path(path),
+            size: None,
+            tint: false,
+        }
+    }
+
+    pub fn from_static(data: &'static [u8]) -> Svg {
+        Svg {
+            source: SvgSource::Static(data),
+            key: SvgKey::Static(data.as_ptr() as usize, data.len()),
+            size: None,
+            tint: false,
+        }
+    }
+
+    /// Sets the frame size in logical pixels, by default the svg's own size is used
+    pub fn size(self, width: f64, height: f64) -> Svg {
+        Svg {
+            size: Some(Size::new(width, height)),
+            ..self
+        }
+    }
+
+    /// Paints the whole icon with the foreground color instead of its own fills and strokes
+    pub fn tint(self, tint: bool) -> Svg {
+        Svg { tint, ..self }
+    }
+
+    /// Parses the svg if it isn't cached yet, returning why it can't be drawn
+    ///
+    /// Views that fail to load draw nothing, call this up front to report the error.
+    pub fn load(&self) -> io::Result<()> {
+        match self.cached() {
+            Ok(_) => Ok(()),
+            Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+        }
+    }
+
+    fn cached(&self) -> Result<Arc<ParsedSvg>, Arc<io::Error>> {
+        if let Some(parsed) = SVG_CACHE.read().unwrap().get(&self.key) {
+            return parsed.clone();
+        }
+
+        let parsed = ParsedSvg::parse(&self.source)
+            .map(Arc::new)
+            .map_err(Arc::new);
+
+        SVG_CACHE
+            .write()
+            .unwrap()
+            .insert(self.key.clone(), parsed.clone());
+
+        parsed
+    }
+
+    fn parsed(&self) -> Option<Arc<ParsedSvg>> {
+        self.cached().ok()
+    }
+}
+
+impl Element for Svg {}
+
+impl View for Svg {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let size = self
+            .size
+            .or_else(|| self.parsed().map(|parsed| parsed.size))
+            .unwrap_or(Size::ZERO);
+
+        let rect = Rect::from_origin_size(
+            available_rect.origin(),
+            (
+                size.width * lctx.scale_factor,
+                size.height * lctx.scale_factor,
+            ),
+        );
+
+        get_id_manger_mut().set_layout_content_rect(lctx.id(), rect);
+
+        rect
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        let bounds = get_id_manger().get_layout(dctx.id()).content_bounds;
+
+        if dctx.is_clipped(bounds) {
+            return;
+        }
+
+        let Some(parsed) = self.parsed() else { return };
+        if parsed.view_box.width() <= 0.0 || parsed.view_box.height() <= 0.0 {
+            return;
+        }
+
+        // Map the view box onto the frame
+        let transform = dctx.transform
+            * Affine::translate(bounds.origin().to_vec2())
+            * Affine::scale_non_uniform(
+                bounds.width() / parsed.view_box.width(),
+                bounds.height() / parsed.view_box.height(),
+            )
+            * Affine::translate(-parsed.view_box.origin().to_vec2());
+
+        let mut builder = dctx.builder.borrow_mut();
+
+        if !self.tint {
+            builder.append(&parsed.fragment, Some(transform));
+            return;
+        }
+
+        // Keep the icon's coverage but replace its colors, by painting the foreground over it with `SrcIn`
+        builder.push_layer(BlendMode::default(), 1.0, dctx.transform, &bounds);
+        builder.append(&parsed.fragment, Some(transform));
+        builder.push_layer(
+            BlendMode::new(Mix::Normal, Compose::SrcIn),
+            1.0,
+            dctx.transform,
+            &bounds,
+        );
+        builder.fill(
+            vello::peniko::Fill::NonZero,
+            dctx.transform,
+            &dctx.env::<ForegroundColor>(),
+            None,
+            &bounds,
+        );
+        builder.pop_layer();
+        builder.pop_layer();
+    }
+}
@@ -0,0 +1,247 @@
+use vello::{kurbo::Rect, peniko::Color};
+
+use crate::{
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    environment::{EnvironmentKey, EnvironmentValues, FillBrush, ForegroundColor, TextSettings},
+    event::Event,
+    view::{Element, View},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Colors named for what they're used for rather than what they look like, so views work in either scheme
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeColors {
+    /// Behind everything in the window
+    pub background: Color,
+    /// Behind grouped content, like cards and sidebars
+    pub surface: Color,
+    pub text: Color,
+    /// Interactive and selected elements
+    pub accent: Color,
+    pub border: Color,
+}
+
+/// Font sizes in logical pixels, smallest to largest
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Typography {
+    pub caption: f32,
+    pub body: f32,
+    pub headline: f32,
+    pub title: f32,
+    pub large_title: f32,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            caption: 14.0,
+            body: 20.0,
+            headline: 24.0,
+            title: 28.0,
+            large_title: 34.0,
+        }
+    }
+}
+
+/// Distances in logical pixels, smallest to largest
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpacingScale {
+    pub extra_small: f64,
+    /// Between the children of stacks that don't set their own spacing
+    pub small: f64,
+    pub medium: f64,
+    pub large: f64,
+    pub extra_large: f64,
+}
+
+impl Default for SpacingScale {
+    fn default() -> Self {
+        Self {
+            extra_small: 2.0,
+            small: 5.0,
+            medium: 10.0,
+            large: 20.0,
+            extra_large: 40.0,
+        }
+    }
+}
+
+/// A level of the typography scale, applied with the `type_style` modifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeStyle {
+    Caption,
+    Body,
+    Headline,
+    Title,
+    LargeTitle,
+}
+
+impl Typography {
+    pub fn size(&self, style: TypeStyle) -> f32 {
+        match style {
+            TypeStyle::Caption => self.caption,
+            TypeStyle::Body => self.body,
+            TypeStyle::Headline => self.headline,
+            TypeStyle::Title => self.title,
+            TypeStyle::LargeTitle => self.large_title,
+        }
+    }
+}
+
+/// The colors, font sizes and spacing views fall back to, read from the environment with `env::<Theme>()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub scheme: ColorScheme,
+    pub colors: ThemeColors,
+    pub typography: Typography,
+    pub spacing: SpacingScale,
+}
+
+impl Theme {
+    pub fn light() -> Theme {
+        Theme {
+            scheme: ColorScheme::Light,
+            colors: ThemeColors {
+                background: Color::rgb8(255, 255, 255),
+                surface: Color::rgb8(242, 242, 247),
+                text: Color::rgb8(0, 0, 0),
+                accent: Color::rgb8(0, 122, 255),
+                border: Color::rgb8(198, 198, 200),
+            },
+            typography: Typography::default(),
+            spacing: SpacingScale::default(),
+        }
+    }
+
+    pub fn dark() -> Theme {
+        Theme {
+            scheme: ColorScheme::Dark,
+            colors: ThemeColors {
+                background: Color::rgb8(28, 28, 30),
+                surface: Color::rgb8(44, 44, 46),
+                text: Color::rgb8(255, 255, 255),
+                accent: Color::rgb8(10, 132, 255),
+                border: Color::rgb8(56, 56, 58),
+            },
+            typography: Typography::default(),
+            spacing: SpacingScale::default(),
+        }
+    }
+
+    /// The built in theme for `scheme`
+    pub fn for_scheme(scheme: ColorScheme) -> Theme {
+        match scheme {
+            ColorScheme::Light => Theme::light(),
+            ColorScheme::Dark => Theme::dark(),
+        }
+    }
+
+    /// Sets the theme along with the values it provides defaults for (the foreground and shape fill colors and
+    /// the body font size)
+    pub fn apply(&self, environment: &mut EnvironmentValues) {
+        environment.set::<Theme>(self.clone());
+        environment.set::<ForegroundColor>(self.colors.text.into());
+        environment.set::<FillBrush>(self.colors.text.into());
+        environment.update::<TextSettings>(|text| text.size = self.typography.body);
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+impl EnvironmentKey for Theme {
+    type Value = Theme;
+
+    fn default_value() -> Theme {
+        Theme::default()
+    }
+}
+
+/// Switches the view's subtree to another theme
+pub struct Themed<V: View> {
+    view: V,
+    theme: Theme,
+}
+
+impl<V: View> Element for Themed<V> {}
+
+impl<V: View> View for Themed<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let mut environment = lctx.environment.clone();
+        self.theme.apply(&mut environment);
+
+        let previous = std::mem::replace(&mut lctx.environment, environment);
+        let rect = self.view.layout(lctx, available_rect);
+        lctx.environment = previous;
+
+        rect
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        self.theme.apply(&mut dctx.environment);
+
+        self.view.draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+/// Sets the font size of any text in the view's subtree from the theme's typography scale
+pub struct TypeStyled<V: View> {
+    view: V,
+    style: TypeStyle,
+}
+
+impl<V: View> Element for TypeStyled<V> {}
+
+impl<V: View> View for TypeStyled<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let size = lctx.env::<Theme>().typography.size(self.style);
+        let mut environment = lctx.environment.clone();
+        environment.update::<TextSettings>(|text| text.size = size);
+
+        let previous = std::mem::replace(&mut lctx.environment, environment);
+        let rect = self.view.layout(lctx, available_rect);
+        lctx.environment = previous;
+
+        rect
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let size = dctx.env::<Theme>().typography.size(self.style);
+        dctx.environment
+            .update::<TextSettings>(|text| text.size = size);
+
+        self.view.draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait ThemeImpl<T: View> {
+    fn theme(self, theme: Theme) -> Themed<T>;
+    fn type_style(self, style: TypeStyle) -> TypeStyled<T>;
+}
+
+impl<T: View> ThemeImpl<T> for T {
+    fn theme(self, theme: Theme) -> Themed<T> {
+        Themed { view: self, theme }
+    }
+
+    fn type_style(self, style: TypeStyle) -> TypeStyled<T> {
+        TypeStyled { view: self, style }
+    }
+}
@@ -1,13 +1,20 @@
 use dui_macros::{multi, multi_from};
 use vello::{
-    kurbo::{Affine, Insets, Rect, Size},
-    peniko::Brush,
+    kurbo::{Affine, BezPath, Insets, Rect, RoundedRect, RoundedRectRadii, Shape as _, Size, Vec2},
+    peniko::{BlendMode, Brush, Color, Compose, Mix},
 };
 
 use crate::{
-    defaults::DEFAULT_SPACING,
-    drawing::{DrawingContext, LayoutContext},
+    drawing::{DrawingContext, EventContext, LayoutContext},
+    environment::{BackgroundBrush, FillBrush, ForegroundColor, ShapeStroke, TextSettings},
+    event::Event,
     layout::{get_id_manger, get_id_manger_mut},
+    paint::Paint,
+    shape::{draw_path, BoundsShape, Shape, StrokeStyle, SHAPE_TOLERANCE},
+    simple_text::{
+        FontWeight, TextAlignment, TextDecoration, TextEnvironment, TextLayout, TruncationMode,
+    },
+    theme::Theme,
     Alignment, HorizontalAlignment, VerticalALignment,
 };
 
@@ -37,12 +44,17 @@ impl View for () {
     }
 
     fn draw(&self, _dctx: DrawingContext) {}
+
+    fn event(&self, _ectx: EventContext, _event: &Event) -> bool {
+        false
+    }
 }
 
 pub trait ElementIterator {
     fn len(&self) -> usize;
     fn layout_at(&self, lctx: &mut LayoutContext, available_rect: Rect, index: usize) -> Rect;
     fn draw_at(&self, dctx: DrawingContext, index: usize);
+    fn event_at(&self, ectx: EventContext, event: &Event, index: usize) -> bool;
     fn is_leaf_at(&self, index: usize) -> bool;
 }
 
@@ -68,6 +80,10 @@ impl<V: View> ElementIterator for V {
         self.draw(dctx)
     }
 
+    fn event_at(&self, ectx: EventContext, event: &Event, _index: usize) -> bool {
+        self.event(ectx, event)
+    }
+
     fn is_leaf_at(&self, _index: usize) -> bool {
         false
     }
@@ -81,20 +97,33 @@ pub trait View: Element {
     fn draw(&self, dctx: DrawingContext) {
         self.body().draw(dctx);
     }
+
+    /// Handles an input event, returning `true` if it was consumed and the view needs to be redrawn
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.body().event(ectx, event)
+    }
 }
 
 pub struct VStack<E: ElementIterator> {
-    spacing: f64,
+    /// Logical pixels between children, `None` for the theme's small spacing
+    spacing: Option<f64>,
     element: E,
 }
 
 impl<E: ElementIterator> VStack<E> {
     pub fn new(element: impl Into<E>) -> VStack<E> {
         VStack {
-            spacing: DEFAULT_SPACING,
+            spacing: None,
             element: element.into(),
         }
     }
+
+    pub fn spacing(self, spacing: f64) -> VStack<E> {
+        VStack {
+            spacing: Some(spacing),
+            ..self
+        }
+    }
 }
 
 multi_from!(VStack, Multi, 2);
@@ -117,10 +146,14 @@ impl<E: ElementIterator> Element for VStack<E> {
 impl<E: ElementIterator> View for VStack<E> {
     fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
         let mut used_rect = available_rect;
-        used_rect.y1 = 0.0;
+        used_rect.y1 = used_rect.y0;
 
         let mut current_rect = available_rect;
         let mut max_width = 0.0;
+        let spacing = self
+            .spacing
+            .unwrap_or_else(|| lctx.env::<Theme>().spacing.small)
+            * lctx.scale_factor;
 
         // Rc::get_mut(&mut lctx.path).unwrap().push(0);
         lctx.path.push(0);
@@ -135,8 +168,8 @@ impl<E: ElementIterator> View for VStack<E> {
             current_rect.y0 += layout.height();
 
             if i != self.element.len() - 1 {
-                used_rect.y1 += self.spacing * lctx.scale_factor;
-                current_rect.y0 += self.spacing * lctx.scale_factor;
+                used_rect.y1 += spacing;
+                current_rect.y0 += spacing;
             }
 
             if layout.width() > max_width {
@@ -162,26 +195,53 @@ impl<E: ElementIterator> View for VStack<E> {
             // *Rc::get_mut(&mut dctx.path).unwrap().last_mut().unwrap() = i as u32;
             pushed.set_last(i as u32);
 
+            // Skip children that are scrolled or clipped out of view
+            if dctx.is_clipped(get_id_manger().get_layout(dctx.id()).paint_bounds()) {
+                continue;
+            }
+
             self.element.draw_at(dctx.clone(), i)
         }
 
         // Rc::get_mut(&mut dctx.path).unwrap().pop();
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let pushed = ectx.push();
+
+        for i in 0..self.element.len() {
+            pushed.set_last(i as u32);
+
+            if self.element.event_at(ectx.clone(), event, i) {
+                return true;
+            }
+        }
+
+        false
+    }
 }
 
 
 pub struct HStack<E: ElementIterator> {
-    spacing: f64,
+    /// Logical pixels between children, `None` for the theme's small spacing
+    spacing: Option<f64>,
     element: E,
 }
 
 impl<E: ElementIterator> HStack<E> {
     pub fn new(element: impl Into<E>) -> HStack<E> {
         HStack {
-            spacing: DEFAULT_SPACING,
+            spacing: None,
             element: element.into(),
         }
     }
+
+    pub fn spacing(self, spacing: f64) -> HStack<E> {
+        HStack {
+            spacing: Some(spacing),
+            ..self
+        }
+    }
 }
 
 multi_from!(HStack, Multi, 2);
@@ -204,10 +264,14 @@ impl<E: ElementIterator> Element for HStack<E> {
 impl<E: ElementIterator> View for HStack<E> {
     fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
         let mut used_rect = available_rect;
-        used_rect.x1 = 0.0;
+        used_rect.x1 = used_rect.x0;
 
         let mut current_rect = available_rect;
         let mut max_height = 0.0;
+        let spacing = self
+            .spacing
+            .unwrap_or_else(|| lctx.env::<Theme>().spacing.small)
+            * lctx.scale_factor;
 
         // Rc::get_mut(&mut lctx.path).unwrap().push(0);
         lctx.path.push(0);
@@ -222,8 +286,8 @@ impl<E: ElementIterator> View for HStack<E> {
             current_rect.x0 += layout.width();
 
             if i != self.element.len() - 1 {
-                used_rect.x1 += self.spacing * lctx.scale_factor;
-                current_rect.x0 += self.spacing * lctx.scale_factor;
+                used_rect.x1 += spacing;
+                current_rect.x0 += spacing;
             }
 
             if layout.height() > max_height {
@@ -249,11 +313,30 @@ impl<E: ElementIterator> View for HStack<E> {
             // *Rc::get_mut(&mut dctx.path).unwrap().last_mut().unwrap() = i as u32;
             pushed.set_last(i as u32);
 
+            // Skip children that are scrolled or clipped out of view
+            if dctx.is_clipped(get_id_manger().get_layout(dctx.id()).paint_bounds()) {
+                continue;
+            }
+
             self.element.draw_at(dctx.clone(), i)
         }
 
         // Rc::get_mut(&mut dctx.path).unwrap().pop();
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let pushed = ectx.push();
+
+        for i in 0..self.element.len() {
+            pushed.set_last(i as u32);
+
+            if self.element.event_at(ectx.clone(), event, i) {
+                return true;
+            }
+        }
+
+        false
+    }
 }
 
 pub struct Rectangle(pub f64, pub f64);
@@ -275,16 +358,13 @@ impl View for Rectangle {
     }
 
     fn draw(&self, dctx: DrawingContext) {
-        let binding = get_id_manger();
-        let layout = binding.get_layout(dctx.id());
+        let bounds = get_id_manger().get_layout(dctx.id()).content_bounds;
 
-        dctx.builder.borrow_mut().fill(
-            vello::peniko::Fill::NonZero,
-            Affine::IDENTITY,
-            &dctx.fill_brush,
-            None,
-            &layout.content_bounds,
-        );
+        if dctx.is_clipped(bounds) {
+            return;
+        }
+
+        draw_path(&dctx, &self.path(bounds, dctx.scale_factor), true);
     }
 }
 
@@ -388,8 +468,8 @@ impl<E: View> View for Padding<E> {
 
         dctx.builder.borrow_mut().fill(
             vello::peniko::Fill::NonZero,
-            Affine::IDENTITY,
-            &dctx.background_brush,
+            dctx.transform,
+            &dctx.env::<BackgroundBrush>(),
             None,
             &layout.content_bounds,
         );
@@ -398,6 +478,12 @@ impl<E: View> View for Padding<E> {
 
         self.element.draw(dctx);
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let _pushed = ectx.push();
+
+        self.element.event(ectx, event)
+    }
 }
 
 pub trait PaddingImpl<T: View> {
@@ -413,11 +499,212 @@ impl<T: View> PaddingImpl<T> for T {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
 pub struct Border<E: View> {
     element: E,
 
     brush: Brush,
+    /// Brushes for each edge in `Insets` order (left, top, right, bottom), overriding `brush`
+    edge_brushes: Option<[Brush; 4]>,
     edges: Insets,
+    radii: RoundedRectRadii,
+    style: BorderStyle,
+}
+
+impl<E: View> Border<E> {
+    pub fn corner_radius(self, radii: impl Into<RoundedRectRadii>) -> Border<E> {
+        Border {
+            radii: radii.into(),
+            ..self
+        }
+    }
+
+    pub fn style(self, style: BorderStyle) -> Border<E> {
+        Border { style, ..self }
+    }
+
+    pub fn edge_brushes(
+        self,
+        left: impl Into<Brush>,
+        top: impl Into<Brush>,
+        right: impl Into<Brush>,
+        bottom: impl Into<Brush>,
+    ) -> Border<E> {
+        Border {
+            edge_brushes: Some([left.into(), top.into(), right.into(), bottom.into()]),
+            ..self
+        }
+    }
+
+    fn edge_brush(&self, edge: usize) -> &Brush {
+        self.edge_brushes
+            .as_ref()
+            .map(|brushes| &brushes[edge])
+            .unwrap_or(&self.brush)
+    }
+
+    /// Fills the ring between the outer and inner rounded rects, clipping it per edge if they differ in brush
+    fn draw_solid(
+        &self,
+        dctx: &DrawingContext,
+        outer: Rect,
+        widths: Insets,
+        radii: RoundedRectRadii,
+    ) {
+        let inner = outer - widths;
+        let inner_radii = RoundedRectRadii::new(
+            (radii.top_left - widths.x0.max(widths.y0)).max(0.0),
+            (radii.top_right - widths.x1.max(widths.y0)).max(0.0),
+            (radii.bottom_right - widths.x1.max(widths.y1)).max(0.0),
+            (radii.bottom_left - widths.x0.max(widths.y1)).max(0.0),
+        );
+
+        let mut ring = RoundedRect::from_rect(outer, radii).to_path(SHAPE_TOLERANCE);
+        ring.extend(RoundedRect::from_rect(inner, inner_radii).path_elements(SHAPE_TOLERANCE));
+
+        let mut builder = dctx.builder.borrow_mut();
+
+        if self.edge_brushes.is_none() {
+            builder.fill(
+                vello::peniko::Fill::EvenOdd,
+                dctx.transform,
+                &self.brush,
+                None,
+                &ring,
+            );
+            return;
+        }
+
+        // Each edge owns the trapezoid between its outer and inner corners
+        let edge_widths = [widths.x0, widths.y0, widths.x1, widths.y1];
+        let quads = [
+            [
+                (outer.x0, outer.y0),
+                (inner.x0, inner.y0),
+                (inner.x0, inner.y1),
+                (outer.x0, outer.y1),
+            ],
+            [
+                (outer.x0, outer.y0),
+                (outer.x1, outer.y0),
+                (inner.x1, inner.y0),
+                (inner.x0, inner.y0),
+            ],
+            [
+                (outer.x1, outer.y0),
+                (outer.x1, outer.y1),
+                (inner.x1, inner.y1),
+                (inner.x1, inner.y0),
+            ],
+            [
+                (outer.x0, outer.y1),
+                (inner.x0, inner.y1),
+                (inner.x1, inner.y1),
+                (outer.x1, outer.y1),
+            ],
+        ];
+
+        for (edge, quad) in quads.iter().enumerate() {
+            if edge_widths[edge] <= 0.0 {
+                continue;
+            }
+
+            let mut clip = BezPath::new();
+            clip.move_to(quad[0]);
+            clip.line_to(quad[1]);
+            clip.line_to(quad[2]);
+            clip.line_to(quad[3]);
+            clip.close_path();
+
+            builder.push_layer(Mix::Clip, 1.0, dctx.transform, &clip);
+            builder.fill(
+                vello::peniko::Fill::EvenOdd,
+                dctx.transform,
+                self.edge_brush(edge),
+                None,
+                &ring,
+            );
+            builder.pop_layer();
+        }
+    }
+
+    /// Strokes the center line of the border with a dash pattern proportional to the width
+    fn draw_dashed(
+        &self,
+        dctx: &DrawingContext,
+        outer: Rect,
+        widths: Insets,
+        radii: RoundedRectRadii,
+    ) {
+        let stroke = |width: f64| {
+            let pattern = match self.style {
+                BorderStyle::Dotted => [width, width],
+                _ => [width * 3.0, width * 2.0],
+            };
+
+            vello::peniko::Stroke::new(width as f32)
+                .with_dashes(0.0, pattern.iter().map(|dash| *dash as f32))
+        };
+
+        let mut builder = dctx.builder.borrow_mut();
+
+        let uniform = widths.x0 == widths.y0 && widths.y0 == widths.x1 && widths.x1 == widths.y1;
+        if uniform && self.edge_brushes.is_none() {
+            let width = widths.x0;
+            let center = outer - Insets::uniform(width / 2.0);
+            let radii = RoundedRectRadii::new(
+                (radii.top_left - width / 2.0).max(0.0),
+                (radii.top_right - width / 2.0).max(0.0),
+                (radii.bottom_right - width / 2.0).max(0.0),
+                (radii.bottom_left - width / 2.0).max(0.0),
+            );
+
+            builder.stroke(
+                &stroke(width),
+                dctx.transform,
+                &self.brush,
+                None,
+                &RoundedRect::from_rect(center, radii),
+            );
+            return;
+        }
+
+        // Differing edges are stroked separately along straight center lines, ignoring the corner radii
+        let center = outer
+            - Insets::new(
+                widths.x0 / 2.0,
+                widths.y0 / 2.0,
+                widths.x1 / 2.0,
+                widths.y1 / 2.0,
+            );
+        let edge_widths = [widths.x0, widths.y0, widths.x1, widths.y1];
+        let lines = [
+            vello::kurbo::Line::new((center.x0, outer.y0), (center.x0, outer.y1)),
+            vello::kurbo::Line::new((outer.x0, center.y0), (outer.x1, center.y0)),
+            vello::kurbo::Line::new((center.x1, outer.y0), (center.x1, outer.y1)),
+            vello::kurbo::Line::new((outer.x0, center.y1), (outer.x1, center.y1)),
+        ];
+
+        for (edge, line) in lines.iter().enumerate() {
+            if edge_widths[edge] <= 0.0 {
+                continue;
+            }
+
+            builder.stroke(
+                &stroke(edge_widths[edge]),
+                dctx.transform,
+                self.edge_brush(edge),
+                None,
+                line,
+            );
+        }
+    }
 }
 
 impl<E: View> Element for Border<E> {
@@ -455,20 +742,37 @@ impl<E: View> View for Border<E> {
     }
 
     fn draw(&self, dctx: DrawingContext) {
-        let binding = get_id_manger();
-        let layout = binding.get_layout(Vec::clone(&dctx.path.borrow()).into());
-
-        dctx.builder.borrow_mut().fill(
-            vello::peniko::Fill::NonZero,
-            Affine::IDENTITY,
-            // &Brush::Solid(dctx.foreground_color),
-            &self.brush,
-            None,
-            &layout.border_bounds,
-        );
+        let outer = get_id_manger().get_layout(dctx.id()).border_bounds;
+
+        if !dctx.is_clipped(outer) {
+            let scale = dctx.scale_factor;
+            let widths = Insets {
+                x0: self.edges.x0 * scale,
+                y0: self.edges.y0 * scale,
+                x1: self.edges.x1 * scale,
+                y1: self.edges.y1 * scale,
+            };
+            let radii = RoundedRectRadii::new(
+                self.radii.top_left * scale,
+                self.radii.top_right * scale,
+                self.radii.bottom_right * scale,
+                self.radii.bottom_left * scale,
+            );
+
+            match self.style {
+                BorderStyle::Solid => self.draw_solid(&dctx, outer, widths, radii),
+                BorderStyle::Dashed | BorderStyle::Dotted => {
+                    self.draw_dashed(&dctx, outer, widths, radii)
+                }
+            }
+        }
 
         self.element.draw(dctx);
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.element.event(ectx, event)
+    }
 }
 
 pub trait BorderImpl<T: View> {
@@ -480,14 +784,17 @@ impl<T: View> BorderImpl<T> for T {
         Border {
             element: self,
             brush: brush.into(),
+            edge_brushes: None,
             edges: edges.into(),
+            radii: RoundedRectRadii::from_single_radius(0.0),
+            style: BorderStyle::Solid,
         }
     }
 }
 
 pub struct Background<V: View> {
     view: V,
-    brush: Brush,
+    paint: Paint,
 }
 
 impl<V: View> Element for Background<V> {}
@@ -498,28 +805,34 @@ impl<V: View> View for Background<V> {
     }
 
     fn draw(&self, mut dctx: DrawingContext) {
-        dctx.background_brush = self.brush.clone();
+        let bounds = get_id_manger().get_layout(dctx.id()).border_bounds;
+        dctx.environment
+            .set::<BackgroundBrush>(self.paint.resolve(bounds));
 
         self.view.draw(dctx);
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
 }
 
 pub trait BackgroundImpl<T: View> {
-    fn background(self, brush: impl Into<Brush>) -> Background<T>;
+    fn background(self, paint: impl Into<Paint>) -> Background<T>;
 }
 
 impl<T: View> BackgroundImpl<T> for T {
-    fn background(self, brush: impl Into<Brush>) -> Background<T> {
+    fn background(self, paint: impl Into<Paint>) -> Background<T> {
         Background {
             view: self,
-            brush: brush.into(),
+            paint: paint.into(),
         }
     }
 }
 
 pub struct Fill<V: View> {
     view: V,
-    brush: Brush,
+    paint: Paint,
 }
 
 impl<V: View> Element for Fill<V> {}
@@ -530,25 +843,514 @@ impl<V: View> View for Fill<V> {
     }
 
     fn draw(&self, mut dctx: DrawingContext) {
-        dctx.fill_brush = self.brush.clone();
+        let bounds = get_id_manger().get_layout(dctx.id()).border_bounds;
+        dctx.environment
+            .set::<FillBrush>(self.paint.resolve(bounds));
 
         self.view.draw(dctx);
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
 }
 
 pub trait FillImpl<T: View> {
-    fn fill(self, brush: impl Into<Brush>) -> Fill<T>;
+    fn fill(self, paint: impl Into<Paint>) -> Fill<T>;
 }
 
 impl<T: View> FillImpl<T> for T {
-    fn fill(self, brush: impl Into<Brush>) -> Fill<T> {
+    fn fill(self, paint: impl Into<Paint>) -> Fill<T> {
         Fill {
             view: self,
-            brush: brush.into(),
+            paint: paint.into(),
         }
     }
 }
 
+/// Sets the brush text, lines and tinted images in the view's subtree are drawn with
+pub struct Foreground<V: View> {
+    view: V,
+    paint: Paint,
+}
+
+impl<V: View> Element for Foreground<V> {}
+
+impl<V: View> View for Foreground<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let bounds = get_id_manger().get_layout(dctx.id()).border_bounds;
+        dctx.environment
+            .set::<ForegroundColor>(self.paint.resolve(bounds));
+
+        self.view.draw(dctx);
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait ForegroundImpl<T: View> {
+    fn foreground(self, paint: impl Into<Paint>) -> Foreground<T>;
+}
+
+impl<T: View> ForegroundImpl<T> for T {
+    fn foreground(self, paint: impl Into<Paint>) -> Foreground<T> {
+        Foreground {
+            view: self,
+            paint: paint.into(),
+        }
+    }
+}
+
+pub struct Stroke<V: View> {
+    view: V,
+    width: f64,
+    paint: Option<Paint>,
+    dash: Vec<f64>,
+}
+
+impl<V: View> Element for Stroke<V> {}
+
+impl<V: View> View for Stroke<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let bounds = get_id_manger().get_layout(dctx.id()).border_bounds;
+        dctx.environment.set::<ShapeStroke>(Some(StrokeStyle {
+            width: self.width,
+            brush: self.paint.as_ref().map(|paint| paint.resolve(bounds)),
+            dash: self.dash.clone(),
+        }));
+
+        self.view.draw(dctx);
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait StrokeImpl<T: View> {
+    /// Outlines shapes in this view with `paint`, `dash` alternates on and off lengths (empty for solid)
+    fn stroke(self, width: f64, paint: impl Into<Paint>, dash: &[f64]) -> Stroke<T>;
+    /// Outlines shapes in this view with the foreground brush they're drawn with
+    fn stroke_foreground(self, width: f64, dash: &[f64]) -> Stroke<T>;
+}
+
+impl<T: View> StrokeImpl<T> for T {
+    fn stroke(self, width: f64, paint: impl Into<Paint>, dash: &[f64]) -> Stroke<T> {
+        Stroke {
+            view: self,
+            width,
+            paint: Some(paint.into()),
+            dash: dash.to_vec(),
+        }
+    }
+
+    fn stroke_foreground(self, width: f64, dash: &[f64]) -> Stroke<T> {
+        Stroke {
+            view: self,
+            width,
+            paint: None,
+            dash: dash.to_vec(),
+        }
+    }
+}
+
+pub struct Clip<V: View, S: Shape> {
+    view: V,
+    shape: S,
+}
+
+impl<V: View, S: Shape> Element for Clip<V, S> {}
+
+impl<V: View, S: Shape> View for Clip<V, S> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let bounds = get_id_manger().get_layout(dctx.id()).border_bounds;
+        let path = self.shape.path(bounds, dctx.scale_factor);
+
+        dctx.clip_to(path.bounding_box());
+
+        dctx.builder
+            .borrow_mut()
+            .push_layer(Mix::Clip, 1.0, dctx.transform, &path);
+
+        self.view.draw(dctx.clone());
+
+        dctx.builder.borrow_mut().pop_layer();
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        // Presses and scrolls over the clipped away part don't reach the content, while moves and releases still
+        // do so drags that started inside can finish
+        if let Event::MouseDown { position, .. } | Event::MouseWheel { position, .. } = event {
+            let bounds = get_id_manger().get_layout(ectx.id()).border_bounds;
+            let path = self.shape.path(bounds, ectx.scale_factor);
+
+            if !path.contains(*position) {
+                return false;
+            }
+        }
+
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait ClipImpl<T: View> {
+    fn clip(self) -> Clip<T, BoundsShape>;
+    fn clip_shape<S: Shape>(self, shape: S) -> Clip<T, S>;
+}
+
+impl<T: View> ClipImpl<T> for T {
+    fn clip(self) -> Clip<T, BoundsShape> {
+        self.clip_shape(BoundsShape)
+    }
+
+    fn clip_shape<S: Shape>(self, shape: S) -> Clip<T, S> {
+        Clip { view: self, shape }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TransformKind {
+    Offset(Vec2),
+    Scale(f64),
+    Rotation(f64),
+    Affine(Affine),
+}
+
+pub struct Transform<V: View> {
+    view: V,
+    kind: TransformKind,
+}
+
+impl<V: View> Transform<V> {
+    /// Resolves the transform against the view's bounds, scaling and rotating happen around the center
+    fn affine(&self, bounds: Rect, scale_factor: f64) -> Affine {
+        let center = bounds.center().to_vec2();
+
+        match self.kind {
+            TransformKind::Offset(offset) => Affine::translate(offset * scale_factor),
+            TransformKind::Scale(scale) => {
+                Affine::translate(center) * Affine::scale(scale) * Affine::translate(-center)
+            }
+            TransformKind::Rotation(angle) => {
+                Affine::translate(center) * Affine::rotate(angle) * Affine::translate(-center)
+            }
+            // Given in logical pixels like offsets, so translations move the same distance on any display
+            TransformKind::Affine(affine) => {
+                Affine::scale(scale_factor) * affine * Affine::scale(1.0 / scale_factor)
+            }
+        }
+    }
+}
+
+impl<V: View> Element for Transform<V> {}
+
+impl<V: View> View for Transform<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        let bounds = get_id_manger().get_layout(dctx.id()).border_bounds;
+
+        dctx.push_transform(self.affine(bounds, dctx.scale_factor));
+
+        self.view.draw(dctx);
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let bounds = get_id_manger().get_layout(ectx.id()).border_bounds;
+        let affine = self.affine(bounds, ectx.scale_factor);
+
+        // Descendants hit test against their untransformed layouts
+        self.view.event(ectx, &event.transformed(affine.inverse()))
+    }
+}
+
+pub trait TransformImpl<T: View> {
+    fn offset(self, x: f64, y: f64) -> Transform<T>;
+    fn scale(self, scale: f64) -> Transform<T>;
+    /// Rotates the view around its center by `angle` radians
+    fn rotation(self, angle: f64) -> Transform<T>;
+    /// Applies `affine` in logical pixels, around the window's origin rather than the view's
+    fn transform(self, affine: Affine) -> Transform<T>;
+}
+
+impl<T: View> TransformImpl<T> for T {
+    fn offset(self, x: f64, y: f64) -> Transform<T> {
+        Transform {
+            view: self,
+            kind: TransformKind::Offset(Vec2::new(x, y)),
+        }
+    }
+
+    fn scale(self, scale: f64) -> Transform<T> {
+        Transform {
+            view: self,
+            kind: TransformKind::Scale(scale),
+        }
+    }
+
+    fn rotation(self, angle: f64) -> Transform<T> {
+        Transform {
+            view: self,
+            kind: TransformKind::Rotation(angle),
+        }
+    }
+
+    fn transform(self, affine: Affine) -> Transform<T> {
+        Transform {
+            view: self,
+            kind: TransformKind::Affine(affine),
+        }
+    }
+}
+
+/// Composites a subtree as one group, so opacity and blending apply to the result rather than every brush
+pub struct Layer<V: View> {
+    view: V,
+    alpha: f32,
+    blend: BlendMode,
+}
+
+impl<V: View> Element for Layer<V> {}
+
+impl<V: View> View for Layer<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        self.view.layout(lctx, available_rect)
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        if self.alpha <= 0.0 {
+            return;
+        }
+
+        // `bounding` is already in window space and covers everything the subtree can show
+        dctx.builder.borrow_mut().push_layer(
+            self.blend,
+            self.alpha,
+            Affine::IDENTITY,
+            &dctx.bounding,
+        );
+
+        self.view.draw(dctx.clone());
+
+        dctx.builder.borrow_mut().pop_layer();
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait LayerImpl<T: View> {
+    fn opacity(self, alpha: f32) -> Layer<T>;
+    fn blend_mode(self, blend: impl Into<BlendMode>) -> Layer<T>;
+}
+
+impl<T: View> LayerImpl<T> for T {
+    fn opacity(self, alpha: f32) -> Layer<T> {
+        Layer {
+            view: self,
+            alpha: alpha.clamp(0.0, 1.0),
+            blend: BlendMode::default(),
+        }
+    }
+
+    fn blend_mode(self, blend: impl Into<BlendMode>) -> Layer<T> {
+        Layer {
+            view: self,
+            alpha: 1.0,
+            blend: blend.into(),
+        }
+    }
+}
+
+/// A blurred copy of the view's bounds drawn underneath it
+pub struct Shadow<V: View> {
+    view: V,
+    color: Color,
+    radius: f64,
+    offset: Vec2,
+    corner_radius: f64,
+}
+
+impl<V: View> Shadow<V> {
+    /// Rounds the shadow's corners, to match a rounded border or clip shape
+    pub fn corner_radius(self, corner_radius: f64) -> Shadow<V> {
+        Shadow {
+            corner_radius,
+            ..self
+        }
+    }
+
+    /// How far the shadow reaches past the bounds, the blur fades out after about 3 standard deviations
+    fn extent(&self, scale_factor: f64) -> Insets {
+        let reach = self.radius * 1.5 * scale_factor;
+        let offset = self.offset * scale_factor;
+
+        Insets {
+            x0: (reach - offset.x).max(0.0),
+            y0: (reach - offset.y).max(0.0),
+            x1: (reach + offset.x).max(0.0),
+            y1: (reach + offset.y).max(0.0),
+        }
+    }
+}
+
+impl<V: View> Element for Shadow<V> {}
+
+impl<V: View> View for Shadow<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let layout = self.view.layout(lctx, available_rect);
+
+        get_id_manger_mut().expand_layout_paint_insets(lctx.id(), self.extent(lctx.scale_factor));
+
+        layout
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        let layout = get_id_manger().get_layout(dctx.id()).border_bounds;
+
+        if !dctx.is_clipped(layout + self.extent(dctx.scale_factor)) {
+            let scale = dctx.scale_factor;
+
+            dctx.builder.borrow_mut().draw_blurred_rounded_rect(
+                dctx.transform,
+                layout + self.offset * scale,
+                self.color,
+                self.corner_radius * scale,
+                self.radius * scale / 2.0,
+            );
+        }
+
+        self.view.draw(dctx);
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait ShadowImpl<T: View> {
+    /// Draws a shadow of `color` blurred by `radius` and moved by `offset`, all in logical pixels
+    fn shadow(self, color: impl Into<Color>, radius: f64, offset: impl Into<Vec2>) -> Shadow<T>;
+}
+
+impl<T: View> ShadowImpl<T> for T {
+    fn shadow(self, color: impl Into<Color>, radius: f64, offset: impl Into<Vec2>) -> Shadow<T> {
+        Shadow {
+            view: self,
+            color: color.into(),
+            radius,
+            offset: offset.into(),
+            corner_radius: 0.0,
+        }
+    }
+}
+
+/// Samples per axis used to approximate a gaussian blur
+const BLUR_SAMPLES: i32 = 3;
+
+/// Blurs the view's contents
+///
+/// Vello has no filter effects and no way to sample a layer, so this draws the subtree once per sample at gaussian
+/// weighted offsets and sums them into a group. A 3x3 grid keeps it at nine times the cost of drawing the view,
+/// which reads as a soft blur for small radii but shows ghosted copies of sharp edges once the radius gets large.
+pub struct Blur<V: View> {
+    view: V,
+    radius: f64,
+}
+
+impl<V: View> Element for Blur<V> {}
+
+impl<V: View> View for Blur<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let layout = self.view.layout(lctx, available_rect);
+
+        get_id_manger_mut().expand_layout_paint_insets(
+            lctx.id(),
+            Insets::uniform(self.radius * lctx.scale_factor),
+        );
+
+        layout
+    }
+
+    fn draw(&self, dctx: DrawingContext) {
+        if self.radius <= 0.0 {
+            self.view.draw(dctx);
+            return;
+        }
+
+        let step = self.radius * dctx.scale_factor / (BLUR_SAMPLES / 2) as f64;
+        let sigma = (BLUR_SAMPLES / 2) as f64 / 1.5;
+
+        let offsets: Vec<(Vec2, f64)> = (0..BLUR_SAMPLES * BLUR_SAMPLES)
+            .map(|i| {
+                let x = (i % BLUR_SAMPLES - BLUR_SAMPLES / 2) as f64;
+                let y = (i / BLUR_SAMPLES - BLUR_SAMPLES / 2) as f64;
+                let weight = (-(x * x + y * y) / (2.0 * sigma * sigma)).exp();
+
+                (Vec2::new(x, y) * step, weight)
+            })
+            .collect();
+        let total: f64 = offsets.iter().map(|(_, weight)| weight).sum();
+
+        dctx.builder.borrow_mut().push_layer(
+            BlendMode::default(),
+            1.0,
+            Affine::IDENTITY,
+            &dctx.bounding,
+        );
+
+        for (offset, weight) in offsets {
+            // Adding the weighted samples together averages them inside the group
+            dctx.builder.borrow_mut().push_layer(
+                BlendMode::new(Mix::Normal, Compose::Plus),
+                (weight / total) as f32,
+                Affine::IDENTITY,
+                &dctx.bounding,
+            );
+
+            let mut sample = dctx.clone();
+            sample.transform = Affine::translate(offset) * sample.transform;
+            self.view.draw(sample);
+
+            dctx.builder.borrow_mut().pop_layer();
+        }
+
+        dctx.builder.borrow_mut().pop_layer();
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait BlurImpl<T: View> {
+    /// Blurs the view by `radius` logical pixels
+    fn blur(self, radius: f64) -> Blur<T>;
+}
+
+impl<T: View> BlurImpl<T> for T {
+    fn blur(self, radius: f64) -> Blur<T> {
+        Blur { view: self, radius }
+    }
+}
+
 pub struct ExactFrame<V: View> {
     view: V,
     size: Size,
@@ -621,6 +1423,12 @@ impl<V: View> View for ExactFrame<V> {
 
         self.view.draw(dctx);
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let _pushed = ectx.push();
+
+        self.view.event(ectx, event)
+    }
 }
 
 pub struct LoseFrame<V: View> {
@@ -700,6 +1508,12 @@ impl<V: View> View for LoseFrame<V> {
 
         self.view.draw(dctx);
     }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        let _pushed = ectx.push();
+
+        self.view.event(ectx, event)
+    }
 }
 
 pub trait FrameImpl<T: View> {
@@ -726,6 +1540,226 @@ impl<T: View> FrameImpl<T> for T {
     }
 }
 
+enum FontChange {
+    Family(String),
+    Size(f32),
+    Weight(FontWeight),
+    Width(f32),
+    Italic(bool),
+}
+
+impl FontChange {
+    fn apply(&self, text: &mut TextEnvironment) {
+        match self {
+            FontChange::Family(family) => text.family = Some(family.clone()),
+            FontChange::Size(size) => text.size = *size,
+            FontChange::Weight(weight) => text.weight = *weight,
+            FontChange::Width(width) => text.width = *width,
+            FontChange::Italic(italic) => text.italic = *italic,
+        }
+    }
+}
+
+/// Changes the font used by any text in the view's subtree
+pub struct Font<V: View> {
+    view: V,
+    change: FontChange,
+}
+
+impl<V: View> Element for Font<V> {}
+
+impl<V: View> View for Font<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let mut environment = lctx.environment.clone();
+        environment.update::<TextSettings>(|text| self.change.apply(text));
+
+        let previous = std::mem::replace(&mut lctx.environment, environment);
+        let rect = self.view.layout(lctx, available_rect);
+        lctx.environment = previous;
+
+        rect
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        dctx.environment
+            .update::<TextSettings>(|text| self.change.apply(text));
+
+        self.view.draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait FontImpl<T: View> {
+    /// Selects a family registered with the `FontManager`, e.g. `"opensans"`
+    fn font(self, family: impl Into<String>) -> Font<T>;
+    fn font_size(self, size: f32) -> Font<T>;
+    fn font_weight(self, weight: FontWeight) -> Font<T>;
+    /// Width as a percentage of normal, for fonts with a `wdth` axis
+    fn font_width(self, width: f32) -> Font<T>;
+    fn italic(self, italic: bool) -> Font<T>;
+}
+
+impl<T: View> FontImpl<T> for T {
+    fn font(self, family: impl Into<String>) -> Font<T> {
+        Font {
+            view: self,
+            change: FontChange::Family(family.into()),
+        }
+    }
+
+    fn font_size(self, size: f32) -> Font<T> {
+        Font {
+            view: self,
+            change: FontChange::Size(size),
+        }
+    }
+
+    fn font_weight(self, weight: FontWeight) -> Font<T> {
+        Font {
+            view: self,
+            change: FontChange::Weight(weight),
+        }
+    }
+
+    fn font_width(self, width: f32) -> Font<T> {
+        Font {
+            view: self,
+            change: FontChange::Width(width),
+        }
+    }
+
+    fn italic(self, italic: bool) -> Font<T> {
+        Font {
+            view: self,
+            change: FontChange::Italic(italic),
+        }
+    }
+}
+
+enum TextStyleChange {
+    Alignment(TextAlignment),
+    LineLimit(Option<usize>),
+    Truncation(TruncationMode),
+    LineSpacing(f32),
+    Underline(Option<TextDecoration>),
+    Strikethrough(Option<TextDecoration>),
+    Highlight(Option<Color>),
+}
+
+impl TextStyleChange {
+    fn apply(&self, text: &mut TextEnvironment) {
+        match self {
+            TextStyleChange::Alignment(alignment) => text.alignment = *alignment,
+            TextStyleChange::LineLimit(line_limit) => text.line_limit = *line_limit,
+            TextStyleChange::Truncation(truncation) => text.truncation = *truncation,
+            TextStyleChange::LineSpacing(line_spacing) => text.line_spacing = *line_spacing,
+            TextStyleChange::Underline(underline) => text.underline = *underline,
+            TextStyleChange::Strikethrough(strikethrough) => text.strikethrough = *strikethrough,
+            TextStyleChange::Highlight(highlight) => text.highlight = *highlight,
+        }
+    }
+}
+
+/// Changes how any text in the view's subtree is broken into lines, lined up and decorated
+pub struct TextStyle<V: View> {
+    view: V,
+    change: TextStyleChange,
+}
+
+impl<V: View> Element for TextStyle<V> {}
+
+impl<V: View> View for TextStyle<V> {
+    fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
+        let mut environment = lctx.environment.clone();
+        environment.update::<TextSettings>(|text| self.change.apply(text));
+
+        let previous = std::mem::replace(&mut lctx.environment, environment);
+        let rect = self.view.layout(lctx, available_rect);
+        lctx.environment = previous;
+
+        rect
+    }
+
+    fn draw(&self, mut dctx: DrawingContext) {
+        dctx.environment
+            .update::<TextSettings>(|text| self.change.apply(text));
+
+        self.view.draw(dctx)
+    }
+
+    fn event(&self, ectx: EventContext, event: &Event) -> bool {
+        self.view.event(ectx, event)
+    }
+}
+
+pub trait TextStyleImpl<T: View> {
+    fn multiline_alignment(self, alignment: TextAlignment) -> TextStyle<T>;
+    /// Limits text to `limit` lines, truncating the rest. `None` removes a limit set further up
+    fn line_limit(self, limit: impl Into<Option<usize>>) -> TextStyle<T>;
+    fn truncation_mode(self, truncation: TruncationMode) -> TextStyle<T>;
+    /// Extra space between lines, in logical pixels
+    fn line_spacing(self, spacing: f32) -> TextStyle<T>;
+    /// Underlines text, `None` removes an underline set further up
+    fn underline(self, decoration: impl Into<Option<TextDecoration>>) -> TextStyle<T>;
+    fn strikethrough(self, decoration: impl Into<Option<TextDecoration>>) -> TextStyle<T>;
+    /// Fills behind text with `color`, like a highlighter pen
+    fn highlight(self, color: impl Into<Option<Color>>) -> TextStyle<T>;
+}
+
+impl<T: View> TextStyleImpl<T> for T {
+    fn multiline_alignment(self, alignment: TextAlignment) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::Alignment(alignment),
+        }
+    }
+
+    fn line_limit(self, limit: impl Into<Option<usize>>) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::LineLimit(limit.into()),
+        }
+    }
+
+    fn truncation_mode(self, truncation: TruncationMode) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::Truncation(truncation),
+        }
+    }
+
+    fn line_spacing(self, spacing: f32) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::LineSpacing(spacing),
+        }
+    }
+
+    fn underline(self, decoration: impl Into<Option<TextDecoration>>) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::Underline(decoration.into()),
+        }
+    }
+
+    fn strikethrough(self, decoration: impl Into<Option<TextDecoration>>) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::Strikethrough(decoration.into()),
+        }
+    }
+
+    fn highlight(self, color: impl Into<Option<Color>>) -> TextStyle<T> {
+        TextStyle {
+            view: self,
+            change: TextStyleChange::Highlight(color.into()),
+        }
+    }
+}
+
 pub struct Text(String);
 
 impl Text {
@@ -738,16 +1772,30 @@ impl Element for Text {}
 
 impl View for Text {
     fn layout(&self, lctx: &mut LayoutContext, available_rect: Rect) -> Rect {
-        let rect = lctx.font_manager.borrow().layout(
-            None,
-            20.0,
-            lctx.scale_factor as _,
-            available_rect,
-            &self.0,
-        );
+        let id = lctx.id();
+        let width = available_rect.width();
+        let scale = lctx.scale_factor as f32;
+        let text_env = lctx.env::<TextSettings>();
+
+        let valid = get_id_manger()
+            .state::<TextLayout>(id.clone())
+            .map(|layout| layout.is_valid_for(&self.0, &text_env, width, scale))
+            .unwrap_or(false);
+
+        let layout = (!valid).then(|| {
+            lctx.font_manager
+                .borrow()
+                .layout_text(&text_env, scale, width, &self.0)
+        });
+
+        let mut manager = get_id_manger_mut();
+        if let Some(layout) = layout {
+            *manager.state_mut::<TextLayout>(id.clone()) = layout;
+        }
 
-        println!("{:?}", rect);
-        get_id_manger_mut().set_layout_content_rect(lctx.path.clone(), rect);
+        let size = manager.state_mut::<TextLayout>(id.clone()).size;
+        let rect = Rect::from_origin_size(available_rect.origin(), size);
+        manager.set_layout_content_rect(id, rect);
 
         rect
     }
@@ -756,23 +1804,28 @@ impl View for Text {
         let binding = get_id_manger();
         let rect = binding.get_layout(dctx.id());
 
+        if dctx.is_clipped(rect.content_bounds) {
+            return;
+        }
+
         dctx.builder.borrow_mut().fill(
             vello::peniko::Fill::NonZero,
-            Affine::IDENTITY,
-            &dctx.background_brush,
+            dctx.transform,
+            &dctx.env::<BackgroundBrush>(),
             None,
             &rect.content_bounds,
         );
 
-        dctx.font_manager.borrow_mut().add(
+        let Some(layout) = binding.state::<TextLayout>(dctx.id()) else {
+            return;
+        };
+
+        dctx.font_manager.borrow_mut().draw_layout(
             &mut dctx.builder.borrow_mut(),
-            None,
-            20.0,
-            dctx.scale_factor as _,
-            Some(&dctx.foreground_color),
-            Affine::IDENTITY,
-            &self.0,
-            rect.content_bounds,
+            layout,
+            &[&dctx.env::<ForegroundColor>()],
+            dctx.transform,
+            rect.content_bounds.origin(),
         );
     }
 }
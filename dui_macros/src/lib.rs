@@ -66,6 +66,11 @@ pub fn multi(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .map(|s| TokenStream::from_str(&s).unwrap())
         .collect();
 
+    let events: Vec<TokenStream> = (0..ucount)
+        .map(|i| format!("{i} => self.{i}.event(ectx, event)"))
+        .map(|s| TokenStream::from_str(&s).unwrap())
+        .collect();
+
     let from_vals: Vec<TokenStream> = (0..ucount)
         .map(|i| format!("value.{i}"))
         .map(|s| TokenStream::from_str(&s).unwrap())
@@ -104,6 +109,13 @@ pub fn multi(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 }
             }
 
+            fn event_at(&self, ectx: EventContext, event: &Event, index: usize) -> bool {
+                match index {
+                    #(#events),*,
+                    _ => panic!("This Element only has {} children!", #ucount)
+                }
+            }
+
             fn is_leaf_at(&self, index: usize) -> bool {
                 match index {
                     #(#is_leafs),*,
@@ -173,7 +185,7 @@ pub fn multi_from(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
         impl<#ty> From<(#val)> for #impl_type<#multi_name<#val>> {
             fn from(value: (#val)) -> Self {
                 #impl_type {
-                    spacing: crate::defaults::DEFAULT_SPACING,
+                    spacing: None,
                     element: #multi_name::from((#(#from_vals),*)),
                 }
             }
@@ -2,25 +2,40 @@
 
 
 
+use std::time::Instant;
+
 use dui_core::{
-    layout::get_id_manger,
+    button::Button,
+    clipboard::set_clipboard_text,
+    defaults::{
+        DEFAULT_DOUBLE_CLICK_DISTANCE, DEFAULT_DOUBLE_CLICK_INTERVAL, DEFAULT_SCROLL_LINE_HEIGHT,
+    },
+    environment::EnvironmentValues,
+    event::{Event as ViewEvent, Key, Modifiers, MouseButton},
+    layout::get_id_manger_mut,
+    modifier::{ModifierContent, ModifierImpl, ViewModifier},
+    selection::SelectableImpl,
     simple_text::FontManager,
+    theme::{ColorScheme, Theme},
     view::{
-        BackgroundImpl, Element, FrameImpl, PaddingImpl, Text, HStack, View,
-    }, Alignment, platform::{get_color, set_blur},
+        BackgroundImpl, Element, ForegroundImpl, FrameImpl, PaddingImpl, Text, HStack, View,
+    }, Alignment, platform::set_blur,
 };
 use dui_util::Rf;
 use vello::{
-    kurbo::{Affine, Rect},
+    kurbo::{Affine, Point, Rect, Vec2},
     peniko::{Brush, Color},
     util::{RenderContext, RenderSurface},
     RenderParams, Renderer, RendererOptions, Scene, SceneBuilder,
 };
 use winit::{
     dpi::{PhysicalSize, Size},
-    event::{Event, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, MouseButton as WinitMouseButton, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
+    },
     event_loop::EventLoop,
-    window::Window,
+    window::{Theme as WindowTheme, Window},
 };
 
 fn create_window(event_loop: &winit::event_loop::EventLoopWindowTarget<()>) -> Window {
@@ -40,6 +55,17 @@ struct RenderState {
     device: usize,
 }
 
+struct CardStyle;
+
+impl ViewModifier for CardStyle {
+    fn body<V: View>(&self, content: ModifierContent<V>) -> impl View {
+        content
+            .background(Color::GREEN)
+            .padding(5.0)
+            .background(Color::RED)
+    }
+}
+
 struct MyView;
 
 impl Element for MyView {
@@ -47,14 +73,14 @@ impl Element for MyView {
         HStack::from((
 
             Text::new("Hello World")
-                .background(Color::GREEN)
-                .padding(5.0)
-                .background(Color::RED),
+                .selectable()
+                .modifier(CardStyle),
 
             Text::new("Hello World")
-                .background(Color::GREEN)
-                .padding(5.0)
-                .background(Color::RED),
+                .foreground(Color::WHITE)
+                .modifier(CardStyle),
+
+            Button::new(Text::new("Copy"), || set_clipboard_text("Hello World")),
 
         ))
         .padding(1.0)
@@ -75,6 +101,13 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
     window.focus_window();
 
     let font_manager = Rf::new(FontManager::new());
+    let mut cursor_position = Point::ZERO;
+    let mut modifiers = Modifiers::default();
+    // When, where and with which button the last press happened, and how many clicks it made
+    let mut last_click: Option<(Instant, Point, MouseButton, u32)> = None;
+    // Moves are only dispatched while a button is held, nothing reacts to hovering yet
+    let mut held_buttons: Vec<MouseButton> = Vec::new();
+    let mut theme = Theme::for_scheme(window.theme().map(color_scheme).unwrap_or_default());
 
     event_loop.run(move |event, _, _| {
         let resize = |state: &mut RenderState, size: (u32, u32), set: bool| {
@@ -99,12 +132,123 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
             }
         };
 
+        let dispatch = |event: ViewEvent| {
+            let ectx = dui_core::drawing::EventContext {
+                path: Rf::new(vec![0]),
+                scale_factor: window.scale_factor(),
+            };
+
+            if MyView.view().event(ectx, &event) {
+                window.request_redraw();
+            }
+        };
+
         match event {
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::Resized(size) => {
                     resize(state.as_mut().unwrap(), (size.width, size.height), false);
                     window.request_redraw();
                 }
+                WindowEvent::ThemeChanged(window_theme) => {
+                    theme = Theme::for_scheme(color_scheme(window_theme));
+                    window.request_redraw();
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    cursor_position = Point::new(position.x, position.y);
+
+                    if !held_buttons.is_empty() {
+                        dispatch(ViewEvent::MouseMoved {
+                            position: cursor_position,
+                        });
+                    }
+                }
+                WindowEvent::ModifiersChanged(state) => {
+                    modifiers = Modifiers {
+                        shift: state.shift(),
+                        control: state.ctrl(),
+                        alt: state.alt(),
+                        logo: state.logo(),
+                    };
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let button = match button {
+                        WinitMouseButton::Left => MouseButton::Left,
+                        WinitMouseButton::Right => MouseButton::Right,
+                        WinitMouseButton::Middle => MouseButton::Middle,
+                        WinitMouseButton::Other(other) => MouseButton::Other(other),
+                    };
+
+                    match state {
+                        ElementState::Pressed => {
+                            let now = Instant::now();
+                            let distance = DEFAULT_DOUBLE_CLICK_DISTANCE * window.scale_factor();
+                            let click_count = match last_click {
+                                Some((time, position, last_button, count))
+                                    if last_button == button
+                                        && now - time <= DEFAULT_DOUBLE_CLICK_INTERVAL
+                                        && position.distance(cursor_position) <= distance =>
+                                {
+                                    count + 1
+                                }
+                                _ => 1,
+                            };
+                            last_click = Some((now, cursor_position, button, click_count));
+
+                            if !held_buttons.contains(&button) {
+                                held_buttons.push(button);
+                            }
+
+                            dispatch(ViewEvent::MouseDown {
+                                position: cursor_position,
+                                button,
+                                click_count,
+                            });
+
+                            // A press can take the selection or focus away without any view consuming it
+                            window.request_redraw();
+                        }
+                        ElementState::Released => {
+                            held_buttons.retain(|held| *held != button);
+
+                            dispatch(ViewEvent::MouseUp {
+                                position: cursor_position,
+                                button,
+                            });
+                        }
+                    }
+                }
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(code),
+                            ..
+                        },
+                    ..
+                } => {
+                    if let Some(key) = key_from_virtual_keycode(code) {
+                        dispatch(ViewEvent::KeyDown { key, modifiers });
+                    }
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let (delta, precise) = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => (
+                            Vec2::new(x as f64, y as f64)
+                                * DEFAULT_SCROLL_LINE_HEIGHT
+                                * window.scale_factor(),
+                            false,
+                        ),
+                        MouseScrollDelta::PixelDelta(delta) => {
+                            (Vec2::new(delta.x, delta.y), true)
+                        }
+                    };
+
+                    dispatch(ViewEvent::MouseWheel {
+                        position: cursor_position,
+                        delta,
+                        precise,
+                    });
+                }
                 _ => (),
             },
             Event::MainEventsCleared => {
@@ -117,13 +261,21 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
                 let mut path = Vec::with_capacity(512);
                 path.push(0);
 
+                let mut environment = EnvironmentValues::new();
+                theme.apply(&mut environment);
+
                 // let path = Rc::new(path);
 
+                get_id_manger_mut().reset_layout_paint_insets();
+
                 let mut lctx = dui_core::drawing::LayoutContext {
                     font_manager: font_manager.clone(),
 
                     path: &mut path,
                     scale_factor: window.scale_factor(),
+                    environment: environment.clone(),
+
+                    needs_redraw: false,
                 };
 
                 let m = MyView;
@@ -135,12 +287,14 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
                     ),
                 );
 
+                let needs_redraw = lctx.needs_redraw;
+
                 resize(state, (size.width() as _, size.height() as _), true);
 
                 scene_builder.fill(
                     vello::peniko::Fill::NonZero,
                     Affine::IDENTITY,
-                    &Brush::Solid(get_color()),
+                    &Brush::Solid(theme.colors.background),
                     None,
                     &Rect::from_origin_size(
                         (0.0, 0.0),
@@ -172,10 +326,9 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
 
                     font_manager: font_manager.clone(),
 
-                    background_brush: Color::TRANSPARENT.into(),
-                    fill_brush: Color::BLACK.into(),
-                    foreground_color: Color::BLACK.into(),
+                    environment,
 
+                    transform: Affine::IDENTITY,
                     bounding: Rect::from_origin_size(
                         (0.0, 0.0),
                         (state.size.width as f64, state.size.height as f64),
@@ -187,8 +340,6 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
 
                 m.view().draw(dctx);
 
-                println!("{:#?}", get_id_manger());
-
                 let params = RenderParams {
                     width: state.size.width,
                     height: state.size.height,
@@ -219,6 +370,10 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
                 render_ctx.devices[state.device]
                     .device
                     .poll(wgpu::Maintain::Poll);
+
+                if needs_redraw {
+                    window.request_redraw();
+                }
             }
             Event::Resumed => {
                 let mut surface = pollster::block_on(render_ctx.create_surface(
@@ -263,6 +418,43 @@ pub fn run(event_loop: EventLoop<()>, window: Window, mut render_ctx: RenderCont
     });
 }
 
+fn color_scheme(theme: WindowTheme) -> ColorScheme {
+    match theme {
+        WindowTheme::Light => ColorScheme::Light,
+        WindowTheme::Dark => ColorScheme::Dark,
+    }
+}
+
+fn key_from_virtual_keycode(code: VirtualKeyCode) -> Option<Key> {
+    let letters = VirtualKeyCode::A as u32..=VirtualKeyCode::Z as u32;
+    let digits = VirtualKeyCode::Key1 as u32..=VirtualKeyCode::Key0 as u32;
+
+    let key = match code {
+        _ if letters.contains(&(code as u32)) => {
+            Key::Character((b'a' + (code as u32 - VirtualKeyCode::A as u32) as u8) as char)
+        }
+        // Key1 through Key9 come before Key0
+        _ if digits.contains(&(code as u32)) => {
+            Key::Character(char::from_digit((code as u32 - *digits.start() + 1) % 10, 10)?)
+        }
+        VirtualKeyCode::Space => Key::Character(' '),
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => Key::Enter,
+        VirtualKeyCode::Escape => Key::Escape,
+        VirtualKeyCode::Tab => Key::Tab,
+        VirtualKeyCode::Back => Key::Backspace,
+        VirtualKeyCode::Delete => Key::Delete,
+        VirtualKeyCode::Left => Key::Left,
+        VirtualKeyCode::Right => Key::Right,
+        VirtualKeyCode::Up => Key::Up,
+        VirtualKeyCode::Down => Key::Down,
+        VirtualKeyCode::Home => Key::Home,
+        VirtualKeyCode::End => Key::End,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
 pub fn start() {
     let event_loop = EventLoop::new();
     let window = create_window(&event_loop);